@@ -0,0 +1,357 @@
+//Subcommands of the command line tool, whose options are given as `--name value`
+use crate::{
+    bench_sorts, bench_sorts_data,
+    dataset::{self, Element},
+    gen_data,
+    lines::{sort_lines, KeyKind, LineKeys},
+};
+use radix_sort::{
+    distr::{StepUniformU32, StepUniformU64, ZipfU32, ZipfU64},
+    external_sort, sort_file_in_place_at, ExternalSortOptions, RadixSort, RadixSortCopyOnly,
+    Strategy,
+};
+use rand_distr::Standard;
+use rayon::{iter::IndexedParallelIterator, slice::ParallelSlice};
+use std::{error::Error, fs, path::Path, process, str::FromStr};
+
+type Result<T> = std::result::Result<T, Box<dyn Error>>;
+
+pub const USAGE: &str = "\
+usage:
+  sort --type <type> --in <file> [--out <file>] [--memory <size>]
+  sort --lines <integer|float|decimal> --in <file> [--out <file>] [--decimals <digits>]
+       [--field <field>] [--delimiter <byte>]
+  gen --type <type> --size <size> --out <file> [--dist <distribution>] [--seed <seed>]
+  bench --type <type> --size <size> [--dist <distribution>] [--runs <runs>]
+  bench --type <type> --in <file> [--in <file>...]
+  verify --type <type> --in <file>
+
+Files hold elements back to back in native order, or are datasets written by gen.
+sort sorts the input in place unless --out is given, in memory mapped from the file unless
+--memory bounds the memory of an external sort, which needs --out and no dataset header.
+sort --lines sorts text by the number every line starts with, or its field counted from 1
+split by the delimiter or by blanks, read as an i64, an f64 or a fixed-point decimal with
+2 digits after the point by default.
+bench sorts generated data in memory, or the given files in place.
+
+types: u8 u16 u32 u64 u128 usize i8 i16 i32 i64 i128 isize f32 f64, and pairs sorted by
+       their first half such as u64/u64
+distributions: uniform (default), zipf [--exponent <exponent>], step [--steps <steps>],
+               the last two for u32, u64, u32/u32 and u64/u64
+sizes: bytes with an optional suffix such as 500MB or 2GB";
+
+//Runs a subcommand on the arguments following it and exits with an error message if it fails
+pub fn run(command: fn(Options) -> Result<()>, names: &[&str]) {
+    let result = Options::parse(std::env::args().skip(2), names).and_then(command);
+    if let Err(error) = result {
+        eprintln!("error: {error}");
+        process::exit(1);
+    }
+}
+
+pub struct Options {
+    values: Vec<(String, String)>,
+}
+
+impl Options {
+    //Every option has a value and is one of `names`
+    fn parse(args: impl IntoIterator<Item = String>, names: &[&str]) -> Result<Options> {
+        let mut args = args.into_iter();
+        let mut values = Vec::new();
+        while let Some(arg) = args.next() {
+            let name = arg
+                .strip_prefix("--")
+                .filter(|name| names.contains(name))
+                .ok_or_else(|| format!("unknown option {arg}\n{USAGE}"))?;
+            let value = args
+                .next()
+                .ok_or_else(|| format!("missing value of --{name}"))?;
+            values.push((name.to_owned(), value));
+        }
+        Ok(Options { values })
+    }
+
+    //Last value given for the option
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.values
+            .iter()
+            .rev()
+            .find(|(option, _)| option == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    pub fn get_all<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a str> {
+        self.values
+            .iter()
+            .filter(move |(option, _)| option == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    pub fn required(&self, name: &str) -> Result<&str> {
+        Ok(self
+            .get(name)
+            .ok_or_else(|| format!("missing --{name}\n{USAGE}"))?)
+    }
+
+    pub fn parse_or<T: FromStr>(&self, name: &str, default: T) -> Result<T> {
+        match self.get(name) {
+            Some(value) => Ok(value
+                .parse()
+                .map_err(|_| format!("invalid value of --{name}: {value}"))?),
+            None => Ok(default),
+        }
+    }
+
+    pub fn size(&self, name: &str) -> Result<Option<u64>> {
+        self.get(name).map(parse_size).transpose()
+    }
+}
+
+//Bytes with an optional decimal unit, such as 2GB
+pub fn parse_size(text: &str) -> Result<u64> {
+    let number_len = text
+        .find(|c: char| !c.is_ascii_digit() && c != '.')
+        .unwrap_or(text.len());
+    let (number, unit) = text.split_at(number_len);
+    let unit_bytes = match unit.to_ascii_uppercase().as_str() {
+        "" | "B" => 1e0,
+        "K" | "KB" => 1e3,
+        "M" | "MB" => 1e6,
+        "G" | "GB" => 1e9,
+        "T" | "TB" => 1e12,
+        _ => return Err(format!("invalid size {text}").into()),
+    };
+    let number: f64 = number.parse().map_err(|_| format!("invalid size {text}"))?;
+    Ok((number * unit_bytes) as u64)
+}
+
+//Evaluates `$body` with `$T` standing for the element type named by `$element_type`, one of the
+//listed primitives or a pair of them, every primitive by default
+macro_rules! with_element {
+    ($element_type:expr, $T:ident => $body:expr) => {
+        with_element!($element_type,
+            [u8 u16 u32 u64 u128 usize i8 i16 i32 i64 i128 isize f32 f64], $T => $body)
+    };
+    ($element_type:expr, [$($t:ident)*], $T:ident => $body:expr) => {
+        match $element_type {
+            $(name if name == stringify!($t) => {
+                type $T = $t;
+                $body
+            })*
+            $(name if name == concat!(stringify!($t), "/", stringify!($t)) => {
+                type $T = ($t, $t);
+                $body
+            })*
+            name => Err(format!("unknown type {name}").into()),
+        }
+    };
+}
+
+//Evaluates `$body` with `$T` standing for the element type, `$distr` for the distribution chosen
+//by the options and `$name` for its name with its parameters
+macro_rules! with_distribution {
+    ($element_type:expr, $options:expr, $T:ident, $name:ident, $distr:ident => $body:expr) => {
+        match $options.get("dist").unwrap_or("uniform") {
+            "uniform" => {
+                let $name = "uniform".to_owned();
+                let $distr = Standard;
+                with_element!($element_type, $T => $body)
+            }
+            "zipf" => {
+                let exponent: f32 = $options.parse_or("exponent", 1.1)?;
+                let $name = format!("zipf {exponent}");
+                match $element_type {
+                    "u32" | "u32/u32" => {
+                        let $distr = ZipfU32::new(exponent);
+                        with_element!($element_type, [u32], $T => $body)
+                    }
+                    "u64" | "u64/u64" => {
+                        let $distr = ZipfU64::new(exponent);
+                        with_element!($element_type, [u64], $T => $body)
+                    }
+                    name => Err(format!("zipf is not available for {name}").into()),
+                }
+            }
+            "step" => {
+                let steps: u32 = $options.parse_or("steps", 4)?;
+                let $name = format!("step {steps}");
+                match $element_type {
+                    "u32" | "u32/u32" => {
+                        let $distr = StepUniformU32::new(steps);
+                        with_element!($element_type, [u32], $T => $body)
+                    }
+                    "u64" | "u64/u64" => {
+                        let $distr = StepUniformU64::new(steps.into());
+                        with_element!($element_type, [u64], $T => $body)
+                    }
+                    name => Err(format!("step is not available for {name}").into()),
+                }
+            }
+            name => Err(format!("unknown distribution {name}").into()),
+        }
+    };
+}
+
+pub const SORT_OPTIONS: &[&str] = &[
+    "type",
+    "in",
+    "out",
+    "memory",
+    "lines",
+    "decimals",
+    "field",
+    "delimiter",
+];
+
+pub fn sort(options: Options) -> Result<()> {
+    let input = Path::new(options.required("in")?);
+    let output = options.get("out").map(Path::new);
+    if let Some(kind) = options.get("lines") {
+        let kind = match kind {
+            "integer" => KeyKind::Integer,
+            "float" => KeyKind::Float,
+            "decimal" => KeyKind::Decimal(options.parse_or("decimals", 2)?),
+            kind => return Err(format!("unknown kind of lines {kind}").into()),
+        };
+        let field = match options.parse_or("field", 0)? {
+            0 => None,
+            field => Some(field),
+        };
+        let delimiter = match options.get("delimiter").map(str::as_bytes) {
+            Some(&[delimiter]) => Some(delimiter),
+            Some(_) => return Err("delimiter is not a single byte".into()),
+            None => None,
+        };
+        let keys = LineKeys {
+            kind,
+            field,
+            delimiter,
+        };
+        return Ok(sort_lines(input, output.unwrap_or(input), &keys)?);
+    }
+    let memory = options.size("memory")?;
+    with_element!(options.required("type")?, T => sort_file::<T>(input, output, memory))
+}
+
+fn sort_file<T: Element>(input: &Path, output: Option<&Path>, memory: Option<u64>) -> Result<()> {
+    if let Some(memory) = memory {
+        if dataset::detect(input)?.is_some() {
+            return Err("datasets are sorted without --memory".into());
+        }
+        let output = output.ok_or("--memory needs --out")?;
+        let options = ExternalSortOptions::new().memory_budget(memory as usize);
+        return Ok(external_sort::<T>(input, output, &options)?);
+    }
+    let path = match output {
+        Some(output) if !same_file(input, output)? => {
+            fs::copy(input, output)?;
+            output
+        }
+        _ => input,
+    };
+    let offset = dataset::data_offset::<T>(path)?;
+    Ok(sort_file_in_place_at::<T>(path, offset)?)
+}
+
+//Whether both paths name the same file, however they are spelled or linked, as copying a file
+//onto itself would truncate it
+fn same_file(a: &Path, b: &Path) -> Result<bool> {
+    if !b.try_exists()? {
+        return Ok(false);
+    }
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::MetadataExt;
+        let (a, b) = (fs::metadata(a)?, fs::metadata(b)?);
+        Ok(a.dev() == b.dev() && a.ino() == b.ino())
+    }
+    #[cfg(not(unix))]
+    {
+        Ok(fs::canonicalize(a)? == fs::canonicalize(b)?)
+    }
+}
+
+pub const GEN_OPTIONS: &[&str] = &["type", "size", "out", "dist", "seed", "exponent", "steps"];
+
+pub fn gen(options: Options) -> Result<()> {
+    let size = options.size("size")?.ok_or("missing --size")?;
+    let output = Path::new(options.required("out")?);
+    let seed = options.parse_or("seed", 0)?;
+    with_distribution!(options.required("type")?, options, T, name, distr => {
+        let len = (size / size_of::<T>() as u64) as usize;
+        gen_data::<T, _>(len, output, &name, &distr, seed)?;
+        println!("Elements: {len}");
+        Ok(())
+    })
+}
+
+pub const BENCH_OPTIONS: &[&str] = &["type", "size", "dist", "runs", "in", "exponent", "steps"];
+
+pub fn bench(options: Options) -> Result<()> {
+    let element_type = options.required("type")?;
+    let files = options.get_all("in").map(Path::new).collect::<Vec<_>>();
+    if !files.is_empty() {
+        return with_element!(element_type, T => bench_files::<T>(&files));
+    }
+    let size = options.size("size")?.ok_or("missing --size or --in")?;
+    let runs = options.parse_or("runs", 5)?;
+    with_distribution!(element_type, options, T, name, distr => {
+        let results = bench_sorts(size as f32 / 1e9, runs, &distr, &[<[T]>::radix_sort]);
+        println!("\nTYPE: {element_type} DISTRIBUTION: {name} RUNS: {runs}");
+        println!("Sorted {:.1}GB of data in: {:.4}s", size as f32 / 1e9, results[0]);
+        Ok(())
+    })
+}
+
+//Compares the variant picked by `Strategy::Auto` with every variant on copies of the files
+fn bench_files<T: Element + Default>(files: &[&Path]) -> Result<()> {
+    let sorts: [fn(&mut [T]); 9] = [
+        <[T]>::radix_sort,
+        <[T]>::radix_sort0,
+        <[T]>::radix_sort1,
+        <[T]>::radix_sort2,
+        <[T]>::radix_sort3,
+        <[T]>::radix_sort4,
+        <[T]>::radix_sort5,
+        <[T]>::radix_sort6,
+        <[T]>::radix_sort7,
+    ];
+    let results = bench_sorts_data(files, &sorts)?;
+    println!("\nFILES: {}", files.len());
+    for (strategy, time) in [Strategy::Auto]
+        .iter()
+        .chain(&Strategy::VARIANTS)
+        .zip(results)
+    {
+        println!("{strategy:?} sorted the files in: {time:.4}s on average");
+    }
+    Ok(())
+}
+
+pub const VERIFY_OPTIONS: &[&str] = &["type", "in"];
+
+pub fn verify(options: Options) -> Result<()> {
+    let input = Path::new(options.required("in")?);
+    with_element!(options.required("type")?, T => verify_file::<T>(input))
+}
+
+fn verify_file<T: Element>(input: &Path) -> Result<()> {
+    if let Some(header) = dataset::detect(input)? {
+        println!(
+            "Distribution: {} Seed: {}",
+            header.distribution, header.seed
+        );
+    }
+    let data = dataset::load::<T>(input)?;
+    println!("Elements: {}", data.len());
+    match data
+        .par_windows(2)
+        .position_first(|pair| pair[0].radix_key() > pair[1].radix_key())
+    {
+        Some(index) => Err(format!("element {} is out of order", index + 1).into()),
+        None => {
+            println!("SUCCESS - all elements are in order");
+            Ok(())
+        }
+    }
+}
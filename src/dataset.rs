@@ -0,0 +1,459 @@
+//Generated datasets, elements stored back to back after a header describing them
+use radix_sort::{PlainData, RadixDigits};
+use std::{
+    fs::File,
+    io::{self, Read, Seek, Write},
+    path::Path,
+    ptr, slice,
+};
+
+const MAGIC: [u8; 8] = *b"RADIXDAT";
+const VERSION: u16 = 1;
+//Multiple of the alignment of every element, so the elements of a mapped file are aligned
+pub const HEADER_LEN: usize = 64;
+const DISTRIBUTION_LEN: usize = 32;
+
+//Types a dataset can hold, plain data so that elements are read and written as bytes
+pub trait Element: RadixDigits + PlainData {
+    const TAG: u16;
+    //Size of the primitives the element is made of, whose bytes are reversed to change endianness
+    const FIELD_SIZE: usize;
+}
+
+macro_rules! elements {
+    ($($t:ty = $tag:literal),*) => {
+        $(
+            impl Element for $t {
+                const TAG: u16 = $tag;
+                const FIELD_SIZE: usize = size_of::<$t>();
+            }
+
+            impl Element for ($t, $t) {
+                const TAG: u16 = 0x100 | $tag;
+                const FIELD_SIZE: usize = size_of::<$t>();
+            }
+        )*
+    };
+}
+
+elements!(
+    u8 = 1,
+    u16 = 2,
+    u32 = 3,
+    u64 = 4,
+    u128 = 5,
+    usize = 6,
+    i8 = 7,
+    i16 = 8,
+    i32 = 9,
+    i64 = 10,
+    i128 = 11,
+    isize = 12,
+    f32 = 13,
+    f64 = 14
+);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Endianness {
+    Little,
+    Big,
+}
+
+impl Endianness {
+    pub const NATIVE: Endianness = if cfg!(target_endian = "big") {
+        Endianness::Big
+    } else {
+        Endianness::Little
+    };
+}
+
+//Fields of the header are little-endian whatever the endianness of the elements
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Header {
+    pub element_tag: u16,
+    pub element_size: u8,
+    pub endianness: Endianness,
+    pub len: u64,
+    pub distribution: String,
+    pub seed: u64,
+}
+
+fn invalid_data(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+impl Header {
+    pub fn new<T: Element>(len: usize, distribution: &str, seed: u64) -> Header {
+        Header {
+            element_tag: T::TAG,
+            element_size: size_of::<T>() as u8,
+            endianness: Endianness::NATIVE,
+            len: len as u64,
+            distribution: distribution.to_owned(),
+            seed,
+        }
+    }
+
+    pub fn to_bytes(&self) -> io::Result<[u8; HEADER_LEN]> {
+        let distribution = self.distribution.as_bytes();
+        if distribution.len() > DISTRIBUTION_LEN {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "distribution name longer than 32 bytes",
+            ));
+        }
+        let mut bytes = [0; HEADER_LEN];
+        bytes[0..8].copy_from_slice(&MAGIC);
+        bytes[8..10].copy_from_slice(&VERSION.to_le_bytes());
+        bytes[10..12].copy_from_slice(&self.element_tag.to_le_bytes());
+        bytes[12] = self.element_size;
+        bytes[13] = (self.endianness == Endianness::Big) as u8;
+        bytes[16..24].copy_from_slice(&self.len.to_le_bytes());
+        bytes[24..32].copy_from_slice(&self.seed.to_le_bytes());
+        bytes[32..][..distribution.len()].copy_from_slice(distribution);
+        Ok(bytes)
+    }
+
+    pub fn from_bytes(bytes: &[u8; HEADER_LEN]) -> io::Result<Header> {
+        if bytes[0..8] != MAGIC {
+            return Err(invalid_data("not a dataset file"));
+        }
+        let version = u16::from_le_bytes([bytes[8], bytes[9]]);
+        if version != VERSION {
+            return Err(invalid_data("unsupported dataset version"));
+        }
+        let endianness = match bytes[13] {
+            0 => Endianness::Little,
+            1 => Endianness::Big,
+            _ => return Err(invalid_data("unknown endianness")),
+        };
+        let distribution = &bytes[32..];
+        let distribution_len = distribution
+            .iter()
+            .position(|&byte| byte == 0)
+            .unwrap_or(DISTRIBUTION_LEN);
+        Ok(Header {
+            element_tag: u16::from_le_bytes([bytes[10], bytes[11]]),
+            element_size: bytes[12],
+            endianness,
+            len: u64::from_le_bytes(bytes[16..24].try_into().unwrap()),
+            distribution: String::from_utf8(distribution[..distribution_len].to_vec())
+                .map_err(|_| invalid_data("distribution name is not UTF-8"))?,
+            seed: u64::from_le_bytes(bytes[24..32].try_into().unwrap()),
+        })
+    }
+
+    pub fn read(input: &mut impl Read) -> io::Result<Header> {
+        let mut bytes = [0; HEADER_LEN];
+        input.read_exact(&mut bytes)?;
+        Header::from_bytes(&bytes)
+    }
+
+    //Checks the elements are of type T and fill the rest of a file of `file_len` bytes
+    pub fn check<T: Element>(&self, file_len: u64) -> io::Result<()> {
+        if self.element_tag != T::TAG || self.element_size as usize != size_of::<T>() {
+            return Err(invalid_data("elements of another type"));
+        }
+        let data_len = self
+            .len
+            .checked_mul(size_of::<T>() as u64)
+            .and_then(|data_len| data_len.checked_add(HEADER_LEN as u64));
+        if data_len != Some(file_len) {
+            return Err(invalid_data("file length does not match the element count"));
+        }
+        Ok(())
+    }
+
+    //Same as `check`, for elements used as they are stored, in native order
+    pub fn check_native<T: Element>(&self, file_len: u64) -> io::Result<()> {
+        self.check::<T>(file_len)?;
+        if self.endianness != Endianness::NATIVE {
+            return Err(invalid_data("elements of another endianness"));
+        }
+        Ok(())
+    }
+}
+
+//Elements are copied through a buffer of bytes of this size, a multiple of every element size
+const CHUNK_LEN: usize = 1 << 20;
+
+//Writes the elements as they come, so the whole dataset is never held in memory
+pub fn write<T: Element>(
+    path: &Path,
+    len: usize,
+    elements: impl IntoIterator<Item = T>,
+    distribution: &str,
+    seed: u64,
+) -> io::Result<()> {
+    let mut output = File::create(path)?;
+    output.write_all(&Header::new::<T>(len, distribution, seed).to_bytes()?)?;
+    let mut elements = elements.into_iter();
+    let mut chunk = Vec::<T>::with_capacity(CHUNK_LEN / size_of::<T>());
+    let mut written = 0;
+    loop {
+        chunk.clear();
+        chunk.extend(elements.by_ref().take(CHUNK_LEN / size_of::<T>()));
+        if chunk.is_empty() {
+            break;
+        }
+        written += chunk.len();
+        //Elements are plain data, so all of their bytes are initialized
+        output.write_all(unsafe {
+            slice::from_raw_parts(chunk.as_ptr() as *const u8, size_of_val(chunk.as_slice()))
+        })?;
+    }
+    if written != len {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "element count differs from the header",
+        ));
+    }
+    Ok(())
+}
+
+//Reads the elements through a buffer of bytes straight into the vector they are returned in,
+//converting them from the endianness of the file
+pub fn read<T: Element>(path: &Path) -> io::Result<(Header, Vec<T>)> {
+    let mut input = File::open(path)?;
+    let header = Header::read(&mut input)?;
+    header.check::<T>(input.metadata()?.len())?;
+    let len = usize::try_from(header.len).map_err(|_| invalid_data("too many elements"))?;
+    let data = read_elements(&mut input, len, header.endianness)?;
+    Ok((header, data))
+}
+
+//Elements of a dataset, or of a file holding nothing but elements in native order
+pub fn load<T: Element>(path: &Path) -> io::Result<Vec<T>> {
+    if detect(path)?.is_some() {
+        return Ok(read(path)?.1);
+    }
+    let mut input = File::open(path)?;
+    let file_len = input.metadata()?.len();
+    if !file_len.is_multiple_of(size_of::<T>() as u64) {
+        return Err(invalid_data("file does not end on an element boundary"));
+    }
+    let len = usize::try_from(file_len / size_of::<T>() as u64)
+        .map_err(|_| invalid_data("too many elements"))?;
+    read_elements(&mut input, len, Endianness::NATIVE)
+}
+
+//Header of a dataset, None for any other file
+pub fn detect(path: &Path) -> io::Result<Option<Header>> {
+    let mut input = File::open(path)?;
+    let mut magic = [0; MAGIC.len()];
+    match input.read_exact(&mut magic) {
+        Ok(()) if magic == MAGIC => {}
+        Err(error) if error.kind() != io::ErrorKind::UnexpectedEof => return Err(error),
+        _ => return Ok(None),
+    }
+    input.rewind()?;
+    Header::read(&mut input).map(Some)
+}
+
+//Offset of the elements of a dataset checked to hold elements of type T in native order, or 0
+//for any other file
+pub fn data_offset<T: Element>(path: &Path) -> io::Result<usize> {
+    match detect(path)? {
+        Some(header) => {
+            header.check_native::<T>(path.metadata()?.len())?;
+            Ok(HEADER_LEN)
+        }
+        None => Ok(0),
+    }
+}
+
+fn read_elements<T: Element>(
+    input: &mut impl Read,
+    len: usize,
+    endianness: Endianness,
+) -> io::Result<Vec<T>> {
+    let mut data = Vec::<T>::with_capacity(len);
+    let mut buffer = vec![0u8; CHUNK_LEN.min(len * size_of::<T>())];
+    while data.len() < len {
+        let chunk_len = buffer.len().min((len - data.len()) * size_of::<T>());
+        let chunk = &mut buffer[..chunk_len];
+        input.read_exact(chunk)?;
+        if endianness != Endianness::NATIVE {
+            for field in chunk.chunks_exact_mut(T::FIELD_SIZE) {
+                field.reverse();
+            }
+        }
+        //Bytes are copied one by one, so the buffer needs no alignment, and any bytes make a valid
+        //element of plain data
+        unsafe {
+            ptr::copy_nonoverlapping(
+                chunk.as_ptr(),
+                data.as_mut_ptr().add(data.len()) as *mut u8,
+                chunk_len,
+            );
+            data.set_len(data.len() + chunk_len / size_of::<T>());
+        }
+    }
+    Ok(data)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{env, fs, process};
+
+    fn temp_path(name: &str) -> std::path::PathBuf {
+        env::temp_dir().join(format!("radix_sort_dataset_{name}_{}", process::id()))
+    }
+
+    fn header_bytes() -> [u8; HEADER_LEN] {
+        Header::new::<u32>(10, "zipf 1.1", 42).to_bytes().unwrap()
+    }
+
+    #[test]
+    fn header_round_trip() {
+        let header = Header {
+            element_tag: <(u64, u64)>::TAG,
+            element_size: 16,
+            endianness: Endianness::Big,
+            len: 1 << 40,
+            distribution: "d".repeat(DISTRIBUTION_LEN),
+            seed: u64::MAX,
+        };
+        assert_eq!(
+            Header::from_bytes(&header.to_bytes().unwrap()).unwrap(),
+            header
+        );
+        let path = temp_path("round_trip");
+        write(&path, 3, [3u32, 1, 2], "step 4", 7).unwrap();
+        let (header, data) = read::<u32>(&path).unwrap();
+        assert_eq!(header, Header::new::<u32>(3, "step 4", 7));
+        assert_eq!(data, [3, 1, 2]);
+        assert_eq!(detect(&path).unwrap(), Some(header));
+        assert_eq!(data_offset::<u32>(&path).unwrap(), HEADER_LEN);
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn bad_magic() {
+        let mut bytes = header_bytes();
+        bytes[0] = b'X';
+        assert_eq!(
+            Header::from_bytes(&bytes).unwrap_err().kind(),
+            io::ErrorKind::InvalidData
+        );
+        //Files without the magic are not datasets
+        let path = temp_path("bad_magic");
+        fs::write(&path, bytes).unwrap();
+        assert_eq!(detect(&path).unwrap(), None);
+        assert_eq!(data_offset::<u32>(&path).unwrap(), 0);
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn bad_version() {
+        let mut bytes = header_bytes();
+        bytes[8..10].copy_from_slice(&(VERSION + 1).to_le_bytes());
+        assert!(Header::from_bytes(&bytes).is_err());
+        let path = temp_path("bad_version");
+        fs::write(&path, bytes).unwrap();
+        assert!(detect(&path).is_err());
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn wrong_type_tag() {
+        let header = Header::new::<u32>(10, "uniform", 0);
+        let file_len = (HEADER_LEN + 40) as u64;
+        assert!(header.check::<u32>(file_len).is_ok());
+        assert!(header.check::<i32>(file_len).is_err());
+        assert!(header.check::<f32>(file_len).is_err());
+        assert!(header.check::<(u16, u16)>(file_len).is_err());
+        let path = temp_path("wrong_type_tag");
+        write(&path, 2, [1u64, 2], "uniform", 0).unwrap();
+        assert!(read::<f64>(&path).is_err());
+        assert!(data_offset::<i64>(&path).is_err());
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn length_mismatch() {
+        let header = Header::new::<u32>(10, "uniform", 0);
+        assert!(header.check::<u32>((HEADER_LEN + 39) as u64).is_err());
+        assert!(header.check::<u32>((HEADER_LEN + 44) as u64).is_err());
+        assert!(Header::new::<u64>(u64::MAX as usize, "uniform", 0)
+            .check::<u64>(HEADER_LEN as u64)
+            .is_err());
+        let path = temp_path("length_mismatch");
+        write(&path, 3, [1u32, 2, 3], "uniform", 0).unwrap();
+        fs::OpenOptions::new()
+            .append(true)
+            .open(&path)
+            .unwrap()
+            .write_all(&[0; 4])
+            .unwrap();
+        assert!(read::<u32>(&path).is_err());
+        //Elements fewer than the header says are not written as a dataset
+        assert!(write(&path, 3, [1u32, 2], "uniform", 0).is_err());
+        fs::remove_file(&path).unwrap();
+    }
+
+    //Dataset written on a machine of the other endianness, big-endian on a little-endian one
+    fn write_foreign<T: Element>(path: &Path, data: &[T]) {
+        let mut header = Header::new::<T>(data.len(), "uniform", 0);
+        header.endianness = match Endianness::NATIVE {
+            Endianness::Little => Endianness::Big,
+            Endianness::Big => Endianness::Little,
+        };
+        let mut bytes = header.to_bytes().unwrap().to_vec();
+        let payload =
+            unsafe { slice::from_raw_parts(data.as_ptr() as *const u8, size_of_val(data)) };
+        for field in payload.chunks(T::FIELD_SIZE) {
+            bytes.extend(field.iter().rev());
+        }
+        std::fs::write(path, bytes).unwrap();
+    }
+
+    fn foreign_round_trip<T: Element + PartialEq + std::fmt::Debug>(name: &str, data: &[T]) {
+        let path = temp_path(name);
+        write_foreign(&path, data);
+        let (header, read_data) = read::<T>(&path).unwrap();
+        assert_ne!(header.endianness, Endianness::NATIVE);
+        assert_eq!(read_data, data);
+        //Mapped in place, elements would be read as they are stored
+        assert!(data_offset::<T>(&path).is_err());
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn foreign_endianness() {
+        foreign_round_trip(
+            "foreign_u32",
+            &(0..100_000u32)
+                .map(|e| e.wrapping_mul(0x9E37_79B9))
+                .collect::<Vec<_>>(),
+        );
+        foreign_round_trip(
+            "foreign_f64",
+            &(0..100_000)
+                .map(|e| (e as f64 - 5e4) * 1.5e-3)
+                .collect::<Vec<_>>(),
+        );
+        foreign_round_trip(
+            "foreign_u64_u64",
+            &(0..100_000u64)
+                .map(|e| (e.wrapping_mul(0x9E37_79B9_7F4A_7C15), !e))
+                .collect::<Vec<_>>(),
+        );
+    }
+
+    #[test]
+    fn long_distribution_name() {
+        let name = "d".repeat(DISTRIBUTION_LEN + 1);
+        assert_eq!(
+            Header::new::<u32>(0, &name, 0)
+                .to_bytes()
+                .unwrap_err()
+                .kind(),
+            io::ErrorKind::InvalidInput
+        );
+        let path = temp_path("long_distribution_name");
+        assert!(write(&path, 0, [0u32; 0], &name, 0).is_err());
+        let _ = fs::remove_file(&path);
+    }
+}
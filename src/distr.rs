@@ -84,7 +84,6 @@ impl StepUniformU32 {
         let s = (<u32>::MAX / (n + 1)) as usize;
         Self {
             values: (0..<u32>::MAX)
-                .into_iter()
                 .skip(s)
                 .step_by(s)
                 .take(n as usize)
@@ -138,7 +137,6 @@ impl StepUniformU64 {
         let s = (<u64>::MAX / (n + 1)) as usize;
         Self {
             values: (0..<u64>::MAX)
-                .into_iter()
                 .skip(s)
                 .step_by(s)
                 .take(n as usize)
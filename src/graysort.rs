@@ -0,0 +1,200 @@
+//Records in the binary format of gensort from sortbenchmark.org
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use rayon::{
+    iter::{IndexedParallelIterator, ParallelIterator},
+    slice::{ParallelSlice, ParallelSliceMut},
+};
+use std::cmp::Ordering;
+
+pub const RECORD_LEN: usize = 100;
+pub const KEY_LEN: usize = 10;
+const RECORDS_PER_BLOCK: usize = 1 << 14;
+
+const CRC32_TABLE: [u32; 256] = {
+    let mut table = [0; 256];
+    let mut index = 0;
+    while index < 256 {
+        let mut crc = index as u32;
+        let mut bit = 0;
+        while bit < 8 {
+            crc = if crc & 1 == 1 {
+                (crc >> 1) ^ 0xEDB8_8320
+            } else {
+                crc >> 1
+            };
+            bit += 1;
+        }
+        table[index] = crc;
+        index += 1;
+    }
+    table
+};
+
+fn crc32(bytes: &[u8]) -> u32 {
+    !bytes.iter().fold(!0, |crc, &byte| {
+        CRC32_TABLE[((crc ^ byte as u32) & 0xFF) as usize] ^ (crc >> 8)
+    })
+}
+
+//Random key followed by the row id in hex, fillers derived from it and gensort's markers
+fn fill_record(record: &mut [u8], row: u128, rng: &mut StdRng) {
+    const HEX_DIGITS: &[u8; 16] = b"0123456789ABCDEF";
+    rng.fill(&mut record[..KEY_LEN]);
+    record[10..12].copy_from_slice(&[0x00, 0x11]);
+    for (position, digit) in record[12..44].iter_mut().rev().enumerate() {
+        *digit = HEX_DIGITS[(row >> (4 * position) & 0xF) as usize];
+    }
+    record[44..48].copy_from_slice(&[0x88, 0x99, 0xAA, 0xBB]);
+    for (position, filler) in record[48..96].chunks_mut(4).enumerate() {
+        filler.fill(HEX_DIGITS[(row >> (4 * position) & 0xF) as usize]);
+    }
+    record[96..100].copy_from_slice(&[0xCC, 0xDD, 0xEE, 0xFF]);
+}
+
+//Same seed and first row give the same records, whatever the number of threads
+pub fn generate(records: &mut [u8], first_row: u128, seed: u64) {
+    assert!(records.len().is_multiple_of(RECORD_LEN));
+    records
+        .par_chunks_mut(RECORD_LEN * RECORDS_PER_BLOCK)
+        .enumerate()
+        .for_each(|(block_index, block)| {
+            let block_row = first_row + (block_index * RECORDS_PER_BLOCK) as u128;
+            let mut rng = StdRng::seed_from_u64(seed ^ (block_row as u64).rotate_left(32));
+            for (row, record) in (block_row..).zip(block.chunks_mut(RECORD_LEN)) {
+                fill_record(record, row, &mut rng);
+            }
+        });
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Summary {
+    pub records: u64,
+    //Sum of the CRC32 of every record, the same for every permutation of the records
+    pub checksum: u128,
+    pub duplicate_keys: u64,
+    //Index of the first record whose key is smaller than the key before it
+    pub first_unordered: Option<u64>,
+}
+
+impl Summary {
+    //Summaries of consecutive parts of the same data, in order, with the ordering of the last key
+    //of the first part and the first key of the next one
+    pub fn then(self, next: Summary, boundary_ordering: Option<Ordering>) -> Summary {
+        let first_unordered = self.first_unordered.or_else(|| {
+            if boundary_ordering == Some(Ordering::Greater) {
+                Some(self.records)
+            } else {
+                next.first_unordered.map(|index| self.records + index)
+            }
+        });
+        Summary {
+            records: self.records + next.records,
+            checksum: self.checksum.wrapping_add(next.checksum),
+            duplicate_keys: self.duplicate_keys
+                + next.duplicate_keys
+                + (boundary_ordering == Some(Ordering::Equal)) as u64,
+            first_unordered,
+        }
+    }
+}
+
+pub fn validate(records: &[u8]) -> Summary {
+    assert!(records.len().is_multiple_of(RECORD_LEN));
+    let block_len = RECORD_LEN * RECORDS_PER_BLOCK;
+    records
+        .par_chunks(block_len)
+        .map(|block| {
+            let mut summary = Summary::default();
+            let mut previous_key: Option<&[u8]> = None;
+            for record in block.chunks(RECORD_LEN) {
+                let key = &record[..KEY_LEN];
+                match previous_key.map(|previous_key| previous_key.cmp(key)) {
+                    Some(Ordering::Greater) if summary.first_unordered.is_none() => {
+                        summary.first_unordered = Some(summary.records)
+                    }
+                    Some(Ordering::Equal) => summary.duplicate_keys += 1,
+                    _ => {}
+                }
+                summary.checksum = summary.checksum.wrapping_add(crc32(record) as u128);
+                summary.records += 1;
+                previous_key = Some(key);
+            }
+            summary
+        })
+        .collect::<Vec<_>>()
+        .into_iter()
+        .enumerate()
+        .fold(
+            Summary::default(),
+            |summary, (block_index, block_summary)| {
+                let boundary = block_index * block_len;
+                let boundary_ordering = (block_index > 0).then(|| {
+                    records[boundary - RECORD_LEN..][..KEY_LEN].cmp(&records[boundary..][..KEY_LEN])
+                });
+                summary.then(block_summary, boundary_ordering)
+            },
+        )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use radix_sort::sort_records;
+
+    //Sorted records of two blocks and a bit, so that some keys meet at a block boundary
+    fn sorted_records() -> Vec<u8> {
+        let mut records = vec![0; RECORD_LEN * (2 * RECORDS_PER_BLOCK + 100)];
+        generate(&mut records, 0, 7);
+        sort_records(&mut records, RECORD_LEN, 0..KEY_LEN);
+        records
+    }
+
+    #[test]
+    fn sorted() {
+        let summary = validate(&sorted_records());
+        assert_eq!(summary.records, 2 * RECORDS_PER_BLOCK as u64 + 100);
+        assert_eq!(summary.first_unordered, None);
+        assert_eq!(summary.duplicate_keys, 0);
+    }
+
+    #[test]
+    fn disorder_at_block_boundary() {
+        let mut records = sorted_records();
+        let (first, second) = records.split_at_mut(RECORD_LEN * RECORDS_PER_BLOCK);
+        first[RECORD_LEN * (RECORDS_PER_BLOCK - 1)..].swap_with_slice(&mut second[..RECORD_LEN]);
+        let summary = validate(&records);
+        assert_eq!(summary.first_unordered, Some(RECORDS_PER_BLOCK as u64));
+        assert_eq!(summary.duplicate_keys, 0);
+    }
+
+    #[test]
+    fn duplicate_at_block_boundary() {
+        let mut records = sorted_records();
+        let boundary = RECORD_LEN * RECORDS_PER_BLOCK;
+        records.copy_within(
+            boundary - RECORD_LEN..boundary - RECORD_LEN + KEY_LEN,
+            boundary,
+        );
+        let summary = validate(&records);
+        assert_eq!(summary.first_unordered, None);
+        assert_eq!(summary.duplicate_keys, 1);
+    }
+
+    #[test]
+    fn checksum_of_any_order() {
+        let mut records = vec![0; RECORD_LEN * (2 * RECORDS_PER_BLOCK + 100)];
+        generate(&mut records, 0, 7);
+        let summary = validate(&records);
+        let sorted_summary = validate(&sorted_records());
+        assert_eq!(sorted_summary.checksum, summary.checksum);
+        let reversed = records
+            .chunks(RECORD_LEN)
+            .rev()
+            .collect::<Vec<_>>()
+            .concat();
+        assert_eq!(validate(&reversed).checksum, summary.checksum);
+        //A changed byte outside the key changes the checksum
+        records[RECORD_LEN * RECORDS_PER_BLOCK + 50] ^= 1;
+        assert_ne!(validate(&records).checksum, summary.checksum);
+    }
+}
@@ -0,0 +1,4 @@
+pub mod distr;
+mod radix_sort;
+
+pub use radix_sort::*;
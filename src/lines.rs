@@ -0,0 +1,377 @@
+//Text files of one number per line, sorted by radix sorting (key, line offset) pairs
+use radix_sort::{RadixDigits, RadixSort};
+use rayon::{
+    current_num_threads,
+    iter::{IntoParallelIterator, ParallelIterator},
+    slice::ParallelSlice,
+};
+use std::{
+    fs::{self, File},
+    io::{self, BufWriter, Write},
+    ops::Range,
+    path::Path,
+};
+
+const LINES_PER_BUFFER: usize = 1 << 14;
+const LINES_PER_WRITE: usize = 1 << 22;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum KeyKind {
+    Integer,
+    Float,
+    //Fixed-point with this many digits after the decimal point, the rest being ignored
+    Decimal(u32),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct LineKeys {
+    pub kind: KeyKind,
+    //Field the key is read from, counted from 1, the whole line if None
+    pub field: Option<usize>,
+    //Byte between fields, runs of blanks if None
+    pub delimiter: Option<u8>,
+}
+
+//Lines are ordered by the number their key starts with, those without one counting as 0, and
+//lines with equal keys keep their order. Every output line ends with a newline.
+pub fn sort_lines(input: &Path, output: &Path, keys: &LineKeys) -> io::Result<()> {
+    let text = fs::read(input)?;
+    match keys.kind {
+        KeyKind::Integer => sort_by(&text, output, keys, parse_integer),
+        KeyKind::Float => sort_by(&text, output, keys, parse_float),
+        KeyKind::Decimal(decimals) => {
+            sort_by(&text, output, keys, |field| parse_decimal(field, decimals))
+        }
+    }
+}
+
+fn sort_by<K>(
+    text: &[u8],
+    output: &Path,
+    keys: &LineKeys,
+    parse: impl Fn(&[u8]) -> K + Sync,
+) -> io::Result<()>
+where
+    K: RadixDigits + Copy,
+{
+    let mut pairs = line_chunks(text, current_num_threads() * 4)
+        .into_par_iter()
+        .map(|chunk| {
+            let mut pairs = Vec::new();
+            let mut start = chunk.start;
+            while start < chunk.end {
+                let end = line_end(text, start).min(chunk.end);
+                let field = field(&text[start..end], keys);
+                pairs.push((parse(field), start as u64));
+                start = end + 1;
+            }
+            pairs
+        })
+        .collect::<Vec<_>>()
+        .concat();
+    pairs.radix_sort();
+    let mut output = BufWriter::new(File::create(output)?);
+    for pairs in pairs.chunks(LINES_PER_WRITE) {
+        let buffers = pairs
+            .par_chunks(LINES_PER_BUFFER)
+            .map(|pairs| {
+                let mut buffer = Vec::new();
+                for &(_, start) in pairs {
+                    let start = start as usize;
+                    buffer.extend_from_slice(&text[start..line_end(text, start)]);
+                    buffer.push(b'\n');
+                }
+                buffer
+            })
+            .collect::<Vec<_>>();
+        for buffer in buffers {
+            output.write_all(&buffer)?;
+        }
+    }
+    output.flush()
+}
+
+fn line_end(text: &[u8], start: usize) -> usize {
+    text[start..]
+        .iter()
+        .position(|&byte| byte == b'\n')
+        .map_or(text.len(), |len| start + len)
+}
+
+//Consecutive parts of the text, every one made of whole lines
+fn line_chunks(text: &[u8], number_of_chunks: usize) -> Vec<Range<usize>> {
+    let chunk_len = text.len().div_ceil(number_of_chunks.max(1)).max(1);
+    let mut chunks = Vec::new();
+    let mut start = 0;
+    while start < text.len() {
+        let end = (start + chunk_len).min(text.len());
+        let end = if end < text.len() {
+            (line_end(text, end) + 1).min(text.len())
+        } else {
+            end
+        };
+        chunks.push(start..end);
+        start = end;
+    }
+    chunks
+}
+
+fn field<'a>(line: &'a [u8], keys: &LineKeys) -> &'a [u8] {
+    let Some(field) = keys.field else {
+        return line;
+    };
+    let field = match keys.delimiter {
+        Some(delimiter) => line.split(|&byte| byte == delimiter).nth(field - 1),
+        None => line
+            .split(|byte| byte.is_ascii_whitespace())
+            .filter(|field| !field.is_empty())
+            .nth(field - 1),
+    };
+    field.unwrap_or_default()
+}
+
+//Sign of the number the field starts with after any blanks, and the rest of it, a leading plus
+//making no number as in `sort -n`
+fn sign(field: &[u8]) -> (bool, &[u8]) {
+    let start = field
+        .iter()
+        .position(|&byte| byte != b' ' && byte != b'\t')
+        .unwrap_or(field.len());
+    match field[start..].split_first() {
+        Some((b'-', rest)) => (true, rest),
+        _ => (false, &field[start..]),
+    }
+}
+
+//Leading digits, saturating when they do not fit
+fn parse_digits(digits: &[u8], negative: bool) -> (i64, usize) {
+    let mut value = 0i64;
+    let mut len = 0;
+    for &byte in digits.iter().take_while(|byte| byte.is_ascii_digit()) {
+        let digit = (byte - b'0') as i64;
+        value = if negative {
+            value.saturating_mul(10).saturating_sub(digit)
+        } else {
+            value.saturating_mul(10).saturating_add(digit)
+        };
+        len += 1;
+    }
+    (value, len)
+}
+
+fn parse_integer(field: &[u8]) -> i64 {
+    let (negative, rest) = sign(field);
+    parse_digits(rest, negative).0
+}
+
+fn parse_decimal(field: &[u8], decimals: u32) -> i64 {
+    let (negative, rest) = sign(field);
+    let (mut value, integer_len) = parse_digits(rest, negative);
+    let fraction = match rest[integer_len..].split_first() {
+        Some((b'.', fraction)) => fraction,
+        _ => &[],
+    };
+    let mut digits = fraction.iter().take_while(|byte| byte.is_ascii_digit());
+    for _ in 0..decimals {
+        let digit = digits.next().map_or(0, |&byte| (byte - b'0') as i64);
+        value = if negative {
+            value.saturating_mul(10).saturating_sub(digit)
+        } else {
+            value.saturating_mul(10).saturating_add(digit)
+        };
+    }
+    value
+}
+
+fn parse_float(field: &[u8]) -> f64 {
+    let (negative, rest) = sign(field);
+    let digits = |from: usize| {
+        rest[from..]
+            .iter()
+            .take_while(|byte| byte.is_ascii_digit())
+            .count()
+    };
+    //Longest prefix that is a number, found in one scan, so that trailing signs or exponent
+    //markers are left out
+    let integer_len = digits(0);
+    let fraction_len = match rest.get(integer_len) {
+        Some(b'.') => digits(integer_len + 1),
+        _ => 0,
+    };
+    if integer_len + fraction_len == 0 {
+        return 0.0;
+    }
+    let mut len = integer_len + fraction_len + (rest.get(integer_len) == Some(&b'.')) as usize;
+    if let Some(b'e' | b'E') = rest.get(len) {
+        let sign_len = matches!(rest.get(len + 1), Some(b'+' | b'-')) as usize;
+        let exponent_len = digits(len + 1 + sign_len);
+        if exponent_len > 0 {
+            len += 1 + sign_len + exponent_len;
+        }
+    }
+    //Digits, signs, points and exponent markers are ASCII
+    let value = std::str::from_utf8(&rest[..len])
+        .ok()
+        .and_then(|number| number.parse::<f64>().ok())
+        .unwrap_or(0.0);
+    //Negative zero is the same key as zero
+    if negative && value != 0.0 {
+        -value
+    } else {
+        value
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{env, process};
+
+    const INTEGERS: LineKeys = LineKeys {
+        kind: KeyKind::Integer,
+        field: None,
+        delimiter: None,
+    };
+
+    #[test]
+    fn integers() {
+        assert_eq!(parse_integer(b"42"), 42);
+        assert_eq!(parse_integer(b" \t-17 apples"), -17);
+        assert_eq!(parse_integer(b"-0"), 0);
+        assert_eq!(parse_integer(b""), 0);
+        assert_eq!(parse_integer(b"-"), 0);
+        //A leading plus makes no number, as in `sort -n`
+        assert_eq!(parse_integer(b"+7"), 0);
+        assert_eq!(parse_integer(b"12345678901234567890123"), i64::MAX);
+        assert_eq!(parse_integer(b"-12345678901234567890123"), i64::MIN);
+        assert_eq!(parse_integer(b"9223372036854775807"), i64::MAX);
+        assert_eq!(parse_integer(b"-9223372036854775808"), i64::MIN);
+    }
+
+    #[test]
+    fn decimals() {
+        assert_eq!(parse_decimal(b"3.14159", 2), 314);
+        //Digits past the precision are cut off, not rounded
+        assert_eq!(parse_decimal(b"0.999", 2), 99);
+        assert_eq!(parse_decimal(b"-2.999", 2), -299);
+        assert_eq!(parse_decimal(b"-0.001", 2), 0);
+        assert_eq!(parse_decimal(b"7", 3), 7000);
+        assert_eq!(parse_decimal(b"-1.5", 3), -1500);
+        assert_eq!(parse_decimal(b" .25x", 2), 25);
+        assert_eq!(parse_decimal(b"5.", 1), 50);
+        assert_eq!(parse_decimal(b"12.34", 0), 12);
+        assert_eq!(parse_decimal(b"+1.5", 2), 0);
+        assert_eq!(parse_decimal(b"92233720368547758.07", 3), i64::MAX);
+        assert_eq!(parse_decimal(b"-92233720368547758.07", 3), i64::MIN);
+    }
+
+    #[test]
+    fn floats() {
+        assert_eq!(parse_float(b"1.5"), 1.5);
+        assert_eq!(parse_float(b"  -2.5e3 kg"), -2500.0);
+        assert_eq!(parse_float(b".5"), 0.5);
+        assert_eq!(parse_float(b"5."), 5.0);
+        assert_eq!(parse_float(b"1E-2"), 0.01);
+        //Exponent markers and signs without digits after them are left out
+        assert_eq!(parse_float(b"3e"), 3.0);
+        assert_eq!(parse_float(b"3e+"), 3.0);
+        assert_eq!(parse_float(b"3.5e-x"), 3.5);
+        assert_eq!(parse_float(b"1-2"), 1.0);
+        assert_eq!(parse_float(b"1.2.3"), 1.2);
+        assert_eq!(parse_float(b"."), 0.0);
+        assert_eq!(parse_float(b"e5"), 0.0);
+        assert_eq!(parse_float(b"+1"), 0.0);
+        assert_eq!(parse_float(b"inf"), 0.0);
+        assert_eq!(parse_float(b"-0").to_bits(), 0.0f64.to_bits());
+        assert_eq!(parse_float(b"-0.0e5").to_bits(), 0.0f64.to_bits());
+        assert_eq!(parse_float(b"1e400"), f64::INFINITY);
+        assert_eq!(parse_float(b"-1e400"), f64::NEG_INFINITY);
+        //A long run of digits is scanned once
+        let digits = vec![b'1'; 1 << 20];
+        assert_eq!(parse_float(&digits), f64::INFINITY);
+    }
+
+    #[test]
+    fn fields() {
+        let blanks = LineKeys {
+            field: Some(2),
+            ..INTEGERS
+        };
+        assert_eq!(field(b"  a \t b c", &blanks), b"b");
+        assert_eq!(field(b"a", &blanks), b"");
+        assert_eq!(field(b"  a \t b c", &INTEGERS), b"  a \t b c");
+        let commas = LineKeys {
+            field: Some(2),
+            delimiter: Some(b','),
+            ..INTEGERS
+        };
+        assert_eq!(field(b"a,b c,d", &commas), b"b c");
+        assert_eq!(field(b"a,,d", &commas), b"");
+        assert_eq!(field(b",x", &commas), b"x");
+        assert_eq!(field(b"a", &commas), b"");
+    }
+
+    #[test]
+    fn chunks_of_lines() {
+        for text in [
+            &b""[..],
+            b"\n",
+            b"1\n22\n333\n",
+            b"1\n22\n333",
+            b"\n\n5\n\n",
+            b"a very long line\nb\n",
+        ] {
+            for number_of_chunks in [0, 1, 2, 3, 7, 100] {
+                let chunks = line_chunks(text, number_of_chunks);
+                assert_eq!(chunks.first().map_or(0, |chunk| chunk.start), 0);
+                assert_eq!(chunks.last().map_or(0, |chunk| chunk.end), text.len());
+                for pair in chunks.windows(2) {
+                    assert_eq!(pair[0].end, pair[1].start);
+                }
+                for chunk in &chunks {
+                    assert!(chunk.start < chunk.end);
+                    assert!(chunk.end == text.len() || text[chunk.end - 1] == b'\n');
+                }
+            }
+        }
+    }
+
+    fn sorted(name: &str, text: &str, keys: &LineKeys) -> String {
+        let input = env::temp_dir().join(format!("radix_sort_lines_{name}_{}", process::id()));
+        let output = input.with_extension("out");
+        fs::write(&input, text).unwrap();
+        sort_lines(&input, &output, keys).unwrap();
+        let sorted = fs::read_to_string(&output).unwrap();
+        fs::remove_file(&input).unwrap();
+        fs::remove_file(&output).unwrap();
+        sorted
+    }
+
+    #[test]
+    fn sorted_lines() {
+        //Empty lines count as 0, and the last line gets a newline
+        assert_eq!(
+            sorted("integers", "3\n\n-2 b\n-0\n+1\n1\n-2 a", &INTEGERS),
+            "-2 b\n-2 a\n\n-0\n+1\n1\n3\n"
+        );
+        assert_eq!(sorted("empty", "", &INTEGERS), "");
+        let keys = LineKeys {
+            kind: KeyKind::Float,
+            field: Some(3),
+            delimiter: Some(b':'),
+        };
+        assert_eq!(
+            sorted("fields", "c:x:1e2\na:y:-1.5\nb:z:.5\nd:w\n", &keys),
+            "a:y:-1.5\nd:w\nb:z:.5\nc:x:1e2\n"
+        );
+        let keys = LineKeys {
+            kind: KeyKind::Decimal(1),
+            field: Some(2),
+            delimiter: None,
+        };
+        assert_eq!(
+            sorted("decimals", "a 1.29\nb  1.21\nc\t1.3\nd 1.1\n", &keys),
+            "d 1.1\na 1.29\nb  1.21\nc\t1.3\n"
+        );
+    }
+}
@@ -1,24 +1,32 @@
-use distr::KeyUniform;
-use radix_sort::{RadixDigits, RadixSort};
-use rand::{thread_rng, Rng};
-use rand_distr::Distribution;
+mod cli;
+mod dataset;
+mod graysort;
+mod lines;
+
+use dataset::Element;
+use radix_sort::{
+    distr::{KeyUniform, StepUniformU32, ZipfU32, ZipfU64},
+    sort_records, CostModel, RadixDigits, RadixSort, RadixSortCopyOnly, RadixSortOptions, Strategy,
+    StrategyCost,
+};
+use rand::{rngs::StdRng, thread_rng, Rng, SeedableRng};
+use rand_distr::{Distribution, Standard};
+use rayon::current_num_threads;
 use std::{
+    array, env,
     fs::File,
-    io::{Read, Write},
-    mem::transmute,
+    io::{self, BufReader, BufWriter, Read, Write},
     path::Path,
+    process,
     time::{Duration, Instant},
 };
 
-mod distr;
-mod radix_sort;
-
 fn bench_sorts<T, U>(size: f32, runs: u32, distr: &U, sorts: &[fn(&mut [T])]) -> Vec<f64>
 where
     T: RadixDigits + Copy,
     U: Distribution<T>,
 {
-    if sorts.len() == 0 || runs == 0 {
+    if sorts.is_empty() || runs == 0 {
         return vec![];
     }
     let size = (size * 1e9 / size_of::<T>() as f32) as usize;
@@ -44,61 +52,80 @@ where
     results.iter().map(|t| (*t / runs).as_secs_f64()).collect()
 }
 
-fn bench_sorts_data<T>(files: &mut [File], sorts: &[fn(&mut [T])]) -> Vec<f64>
+//Sorts a copy of every file loaded into memory, so that the files are left as they are
+fn bench_sorts_data<T>(files: &[&Path], sorts: &[fn(&mut [T])]) -> io::Result<Vec<f64>>
 where
-    T: RadixDigits + Copy,
+    T: Element,
 {
-    if sorts.len() == 0 || files.len() == 0 {
-        return vec![];
+    if sorts.is_empty() || files.is_empty() {
+        return Ok(vec![]);
     }
     let mut results = vec![Duration::ZERO; sorts.len()];
-    for file in files.iter_mut() {
-        let mut data: Vec<T> = {
-            let mut data = Vec::new();
-            file.read_to_end(&mut data).unwrap();
-            unsafe {
-                data.set_len(data.len() / size_of::<T>());
-                transmute(data)
-            }
-        };
-        if sorts.len() == 1 {
+    for file in files {
+        let data = dataset::load::<T>(file)?;
+        for (sort, time) in sorts.iter().zip(&mut results) {
+            let mut clone = data.clone();
             let tick = Instant::now();
-            sorts[0](&mut data);
-            results[0] += tick.elapsed()
-        } else {
-            for (sort, time) in sorts.iter().zip(&mut results) {
-                let mut clone = data.clone();
-                let tick = Instant::now();
-                sort(&mut clone);
-                *time += tick.elapsed()
-            }
+            sort(&mut clone);
+            *time += tick.elapsed()
         }
     }
-    results
+    Ok(results
         .iter()
         .map(|t| (*t / files.len() as u32).as_secs_f64())
-        .collect()
+        .collect())
 }
 
-fn gen_data<T, U>(size: f32, file: &Path, distr: &U)
+fn gen_data<T, U>(
+    len: usize,
+    file: &Path,
+    distribution: &str,
+    distr: &U,
+    seed: u64,
+) -> io::Result<()>
 where
-    T: RadixDigits,
+    T: Element,
     U: Distribution<T>,
 {
-    let size = (size * 1e9 / size_of::<T>() as f32) as usize;
-    let mut data = thread_rng()
-        .sample_iter(distr)
-        .take(size)
-        .collect::<Vec<T>>();
-    let data: Vec<u8> = unsafe {
-        data.set_len(data.len() * size_of::<T>());
-        transmute(data)
-    };
-    let mut file = File::create(file).unwrap();
-    file.write_all(&data).unwrap();
+    let elements = StdRng::seed_from_u64(seed).sample_iter(distr).take(len);
+    dataset::write(file, len, elements, distribution, seed)
 }
 
-fn main() {
+fn calibrate<T, U>(distr: &U) -> CostModel
+where
+    T: RadixDigits + Default + Copy,
+    U: Distribution<T>,
+{
+    const SMALL_SIZE: f32 = 1e-6;
+    const LARGE_SIZE: f32 = 0.2;
+    let number_of_runs = 5;
+    let sorts: [fn(&mut [T]); 8] = [
+        <[T]>::radix_sort0,
+        <[T]>::radix_sort1,
+        <[T]>::radix_sort2,
+        <[T]>::radix_sort3,
+        <[T]>::radix_sort4,
+        <[T]>::radix_sort5,
+        <[T]>::radix_sort6,
+        <[T]>::radix_sort7,
+    ];
+    let small_results = bench_sorts(SMALL_SIZE, number_of_runs, distr, &sorts);
+    let large_results = bench_sorts(LARGE_SIZE, number_of_runs, distr, &sorts);
+    let bytes = |size: f32| (size * 1e9 / size_of::<T>() as f32) as usize * size_of::<T>();
+    CostModel {
+        costs: array::from_fn(|i| {
+            StrategyCost::fit(
+                Strategy::VARIANTS[i],
+                (bytes(SMALL_SIZE), small_results[i]),
+                (bytes(LARGE_SIZE), large_results[i]),
+                T::NUMBER_OF_DIGITS,
+                current_num_threads(),
+            )
+        }),
+    }
+}
+
+fn bench_sizes() {
     let number_of_runs = 5;
     let distribution = KeyUniform;
     let sizes = [0.5, 1., 1.5, 2., 2.5, 3., 3.5, 4.];
@@ -125,3 +152,245 @@ fn main() {
         println!("Sorted {:.1}GB of data in: {:.4}s", size, results[0]);
     }
 }
+
+fn bench_pipelined() {
+    let number_of_runs = 5;
+    let distribution = KeyUniform;
+    let sizes = [0.5, 1., 2., 4.];
+
+    println!("\nTYPE: u64/u64 RUNS: {}", number_of_runs);
+    for size in sizes {
+        let results = bench_sorts(
+            size,
+            number_of_runs,
+            &distribution,
+            &[<[(u64, u64)]>::radix_sort5, <[(u64, u64)]>::radix_sort6],
+        );
+        println!(
+            "Sorted {:.1}GB of data in: {:.4}s (buffered) {:.4}s (pipelined)",
+            size, results[0], results[1]
+        );
+    }
+}
+
+fn bench_hybrid<U: Distribution<(u64, u64)>>(name: &str, distribution: &U) {
+    let number_of_runs = 5;
+    let sizes = [0.5, 1., 2., 4.];
+
+    println!(
+        "\nTYPE: u64/u64 DISTRIBUTION: {} RUNS: {}",
+        name, number_of_runs
+    );
+    for size in sizes {
+        let results = bench_sorts(
+            size,
+            number_of_runs,
+            distribution,
+            &[<[(u64, u64)]>::radix_sort6, <[(u64, u64)]>::radix_sort7],
+        );
+        println!(
+            "Sorted {:.1}GB of data in: {:.4}s (pipelined) {:.4}s (hybrid)",
+            size, results[0], results[1]
+        );
+    }
+}
+
+fn bench_skew<U: Distribution<(u32, u32)>>(name: &str, distribution: &U) {
+    let number_of_runs = 5;
+    let sizes = [0.5, 1., 2.];
+
+    println!(
+        "\nTYPE: u32/u32 DISTRIBUTION: {} RUNS: {}",
+        name, number_of_runs
+    );
+    for size in sizes {
+        let results = bench_sorts(
+            size,
+            number_of_runs,
+            distribution,
+            &[
+                |data| data.radix_sort_with(&RadixSortOptions::new().dominant_runs(false)),
+                |data| data.radix_sort_with(&RadixSortOptions::new()),
+            ],
+        );
+        println!(
+            "Sorted {:.1}GB of data in: {:.4}s (buffered) {:.4}s (dominant runs)",
+            size, results[0], results[1]
+        );
+    }
+}
+
+fn bench_indirect<T>()
+where
+    T: RadixDigits + Copy,
+    Standard: Distribution<T>,
+{
+    let number_of_runs = 5;
+    let size = 0.5;
+    let results = bench_sorts(
+        size,
+        number_of_runs,
+        &Standard,
+        &[
+            |data: &mut [T]| {
+                data.radix_sort_with(&RadixSortOptions::new().indirect_threshold(usize::MAX))
+            },
+            |data: &mut [T]| data.radix_sort_with(&RadixSortOptions::new().indirect_threshold(0)),
+        ],
+    );
+    println!(
+        "Sorted {:.1}GB of {}-byte elements in: {:.4}s (direct) {:.4}s (indirect)",
+        size,
+        size_of::<T>(),
+        results[0],
+        results[1]
+    );
+}
+
+//Written in blocks, so that files larger than memory can be generated
+fn gensort(number_of_records: u64, path: &Path, seed: u64) {
+    const RECORDS_PER_WRITE: u64 = 1 << 20;
+    let mut file = BufWriter::new(File::create(path).unwrap());
+    let mut block = Vec::new();
+    let mut checksum = 0u128;
+    for first_row in (0..number_of_records).step_by(RECORDS_PER_WRITE as usize) {
+        let records = RECORDS_PER_WRITE.min(number_of_records - first_row) as usize;
+        block.resize(records * graysort::RECORD_LEN, 0);
+        graysort::generate(&mut block, first_row as u128, seed);
+        checksum = checksum.wrapping_add(graysort::validate(&block).checksum);
+        file.write_all(&block).unwrap();
+    }
+    file.flush().unwrap();
+    println!("Records: {}", number_of_records);
+    println!("Checksum: {:x}", checksum);
+}
+
+fn valsort(path: &Path, expected_checksum: Option<u128>) {
+    const RECORDS_PER_READ: usize = 1 << 20;
+    let mut file = BufReader::new(File::open(path).unwrap());
+    let mut block = vec![0; RECORDS_PER_READ * graysort::RECORD_LEN];
+    let mut summary = graysort::Summary::default();
+    let mut last_key = None::<[u8; graysort::KEY_LEN]>;
+    loop {
+        let mut len = 0;
+        while len < block.len() {
+            match file.read(&mut block[len..]).unwrap() {
+                0 => break,
+                read => len += read,
+            }
+        }
+        if len == 0 {
+            break;
+        }
+        assert!(
+            len.is_multiple_of(graysort::RECORD_LEN),
+            "file does not end on a record boundary"
+        );
+        let records = &block[..len];
+        let boundary_ordering =
+            last_key.map(|last_key| last_key[..].cmp(&records[..graysort::KEY_LEN]));
+        summary = summary.then(graysort::validate(records), boundary_ordering);
+        last_key = Some(
+            records[len - graysort::RECORD_LEN..][..graysort::KEY_LEN]
+                .try_into()
+                .unwrap(),
+        );
+    }
+    println!("Records: {}", summary.records);
+    println!("Checksum: {:x}", summary.checksum);
+    println!("Duplicate keys: {}", summary.duplicate_keys);
+    match summary.first_unordered {
+        Some(index) => println!("FAILURE - record {} is out of order", index),
+        None if expected_checksum.is_some_and(|checksum| checksum != summary.checksum) => {
+            println!("FAILURE - checksum differs from the input")
+        }
+        None => println!("SUCCESS - all records are in order"),
+    }
+}
+
+fn bench_graysort() {
+    let number_of_runs = 5;
+    let sizes = [0.5, 1., 2.];
+
+    println!("\nRECORDS: gensort 100 bytes RUNS: {}", number_of_runs);
+    for size in sizes {
+        let number_of_records = (size * 1e9) as usize / graysort::RECORD_LEN;
+        let mut time = Duration::ZERO;
+        for run in 0..number_of_runs {
+            let mut records = vec![0; number_of_records * graysort::RECORD_LEN];
+            graysort::generate(&mut records, 0, run);
+            let checksum = graysort::validate(&records).checksum;
+            let tick = Instant::now();
+            sort_records(&mut records, graysort::RECORD_LEN, 0..graysort::KEY_LEN);
+            time += tick.elapsed();
+            let summary = graysort::validate(&records);
+            assert!(summary.first_unordered.is_none() && summary.checksum == checksum);
+        }
+        println!(
+            "Sorted {:.1}GB of data in: {:.4}s",
+            size,
+            (time / number_of_runs as u32).as_secs_f64()
+        );
+    }
+}
+
+fn main() {
+    match env::args().nth(1).as_deref() {
+        Some("calibrate") => {
+            println!("{:#?}", calibrate::<(u64, u64), _>(&KeyUniform));
+        }
+        Some("pipelined") => bench_pipelined(),
+        Some("hybrid") => {
+            bench_hybrid("uniform", &KeyUniform);
+            bench_hybrid("zipf", &ZipfU64::new(1.1));
+        }
+        Some("skew") => {
+            bench_skew("uniform", &KeyUniform);
+            bench_skew("zipf", &ZipfU32::new(1.1));
+            bench_skew("zipf 2", &ZipfU32::new(2.0));
+            bench_skew("step uniform 4", &StepUniformU32::new(4));
+        }
+        Some("indirect") => {
+            println!("\nKEY: u64 RUNS: 5");
+            bench_indirect::<(u64, [u64; 1])>();
+            bench_indirect::<(u64, [u64; 3])>();
+            bench_indirect::<(u64, [u64; 7])>();
+            bench_indirect::<(u64, [u64; 15])>();
+            bench_indirect::<(u64, [u64; 31])>();
+        }
+        Some("gensort") => {
+            let mut args = env::args().skip(2);
+            let number_of_records = args.next().and_then(|arg| arg.parse().ok());
+            let path = args.next();
+            let seed = args.next().map_or(0, |arg| arg.parse().unwrap());
+            match (number_of_records, path) {
+                (Some(number_of_records), Some(path)) => {
+                    gensort(number_of_records, Path::new(&path), seed)
+                }
+                _ => println!("usage: gensort <number of records> <output file> [seed]"),
+            }
+        }
+        Some("valsort") => {
+            let mut args = env::args().skip(2);
+            match args.next() {
+                Some(path) => valsort(
+                    Path::new(&path),
+                    args.next()
+                        .map(|arg| u128::from_str_radix(&arg, 16).unwrap()),
+                ),
+                None => println!("usage: valsort <input file> [checksum of the unsorted data]"),
+            }
+        }
+        Some("graysort") => bench_graysort(),
+        Some("sort") => cli::run(cli::sort, cli::SORT_OPTIONS),
+        Some("gen") => cli::run(cli::gen, cli::GEN_OPTIONS),
+        Some("bench") => cli::run(cli::bench, cli::BENCH_OPTIONS),
+        Some("verify") => cli::run(cli::verify, cli::VERIFY_OPTIONS),
+        Some("help") => println!("{}", cli::USAGE),
+        None => bench_sizes(),
+        Some(command) => {
+            eprintln!("error: unknown subcommand {command}\n{}", cli::USAGE);
+            process::exit(1);
+        }
+    }
+}
@@ -0,0 +1,89 @@
+use super::{runner::Chunks, scratch::TempBuffer, RadixDigits};
+use std::ptr::copy_nonoverlapping;
+
+pub(crate) fn counting_sort_chunks<T: RadixDigits>(data: &mut [T], chunks: &Chunks) {
+    let domain = T::KEY_DOMAIN.expect("key domain too large for a counting sort");
+    let key_histogram_per_chunk = chunks.run(|chunk_index| {
+        let mut key_histogram = vec![0; domain];
+        for element in &data[chunks.range(chunk_index)] {
+            key_histogram[element.radix_key() as usize] += 1;
+        }
+        key_histogram
+    });
+    if T::from_radix_key(0).is_some() {
+        fill_keys(data, chunks, &key_histogram_per_chunk);
+    } else {
+        scatter(data, chunks, key_histogram_per_chunk);
+    }
+}
+
+//Elements are nothing but their key, so they are written back from the counts alone
+fn fill_keys<T: RadixDigits>(
+    data: &mut [T],
+    chunks: &Chunks,
+    key_histogram_per_chunk: &[Vec<usize>],
+) {
+    let mut key_ends = vec![0; key_histogram_per_chunk[0].len()];
+    for key_histogram in key_histogram_per_chunk {
+        for (key_end, key_count) in key_ends.iter_mut().zip(key_histogram) {
+            *key_end += key_count;
+        }
+    }
+    key_ends.iter_mut().fold(0, |key_start, key_count| {
+        *key_count += key_start;
+        *key_count
+    });
+    let dst = &*data;
+    chunks.run(|chunk_index| {
+        let range = chunks.range(chunk_index);
+        let mut position = range.start;
+        let mut key = key_ends.partition_point(|&key_end| key_end <= position);
+        while position < range.end {
+            let run_end = key_ends[key].min(range.end);
+            for target in &dst[position..run_end] {
+                let element = T::from_radix_key(key as u128).expect("key within the domain");
+                unsafe {
+                    (target as *const T as *mut T).write(element);
+                }
+            }
+            position = run_end;
+            key += 1;
+        }
+    });
+}
+
+fn scatter<T: RadixDigits>(
+    data: &mut [T],
+    chunks: &Chunks,
+    mut key_histogram_per_chunk: Vec<Vec<usize>>,
+) {
+    let mut prefix_sum = 0;
+    for key in 0..key_histogram_per_chunk[0].len() {
+        for key_histogram in &mut key_histogram_per_chunk {
+            let new_prefix_sum = prefix_sum + key_histogram[key];
+            key_histogram[key] = prefix_sum;
+            prefix_sum = new_prefix_sum;
+        }
+    }
+    let key_starts_per_chunk = key_histogram_per_chunk;
+    let temp = TempBuffer::<T>::new(data.len());
+    let temp_slice = temp.as_slice();
+    let src = &*data;
+    chunks.run(|chunk_index| {
+        let mut key_starts = key_starts_per_chunk[chunk_index].clone();
+        for element in &src[chunks.range(chunk_index)] {
+            let key = element.radix_key() as usize;
+            unsafe {
+                copy_nonoverlapping(
+                    element,
+                    &temp_slice[key_starts[key]] as *const T as *mut T,
+                    1,
+                );
+            }
+            key_starts[key] += 1;
+        }
+    });
+    unsafe {
+        copy_nonoverlapping(temp_slice.as_ptr(), data.as_mut_ptr(), data.len());
+    }
+}
@@ -0,0 +1,275 @@
+use super::{
+    lsd::{KeyDigits, PassDigits},
+    plain_data::PlainData,
+    RadixDigits, RadixSort, RadixSortOptions,
+};
+use std::{
+    cmp::Ordering,
+    collections::BinaryHeap,
+    env,
+    fs::{self, File},
+    io::{self, BufReader, BufWriter, Read, Write},
+    path::{Path, PathBuf},
+    process, ptr, slice,
+    sync::atomic::{AtomicUsize, Ordering::Relaxed},
+};
+
+//Runs merged at once, keeping the files open by the merge well below common limits
+const MAX_MERGED_RUNS: usize = 64;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ExternalSortOptions {
+    //Bytes of elements and temp memory a run is sorted in, also shared by the readers of the merge
+    pub memory_budget: usize,
+    //Where sorted runs are spilled, the system temp directory by default
+    pub temp_dir: PathBuf,
+    //Options every run is sorted with
+    pub sort: RadixSortOptions,
+}
+
+impl Default for ExternalSortOptions {
+    fn default() -> Self {
+        Self {
+            memory_budget: 1 << 30,
+            temp_dir: env::temp_dir(),
+            sort: RadixSortOptions::from_env_cached().clone(),
+        }
+    }
+}
+
+impl ExternalSortOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn memory_budget(mut self, memory_budget: usize) -> Self {
+        self.memory_budget = memory_budget;
+        self
+    }
+
+    pub fn temp_dir(mut self, temp_dir: impl Into<PathBuf>) -> Self {
+        self.temp_dir = temp_dir.into();
+        self
+    }
+
+    pub fn sort(mut self, sort: RadixSortOptions) -> Self {
+        self.sort = sort;
+        self
+    }
+}
+
+//Sorts a file of elements of T stored back to back, with runs that fit the memory budget
+//sorted in memory and spilled to the temp directory, then merged into the output
+pub fn external_sort<T>(
+    input: &Path,
+    output: &Path,
+    options: &ExternalSortOptions,
+) -> io::Result<()>
+where
+    T: RadixDigits + PlainData,
+{
+    let element_size = size_of::<T>().max(1);
+    //Half of the budget is taken by the temp memory of the sort
+    let run_len = (options.memory_budget / element_size / 2).max(1);
+    let mut input = BufReader::new(File::open(input)?);
+    let mut run = Vec::<T>::with_capacity(run_len);
+    let mut runs = Vec::new();
+    loop {
+        read_elements(&mut input, &mut run, run_len)?;
+        if run.is_empty() {
+            break;
+        }
+        run.radix_sort_with(&options.sort);
+        let last_run = run.len() < run_len;
+        if runs.is_empty() && last_run {
+            //Everything fit into a single run
+            return write_file(output, &run);
+        }
+        let spilled = SpilledRun::create(&options.temp_dir)?;
+        write_file(&spilled.path, &run)?;
+        runs.push(spilled);
+        if last_run {
+            break;
+        }
+    }
+    drop(run);
+    //Groups of consecutive runs are merged into longer ones until they can be merged at once, so
+    //that earlier runs still come first among equal elements
+    while runs.len() > MAX_MERGED_RUNS {
+        let mut merged_runs = Vec::with_capacity(runs.len().div_ceil(MAX_MERGED_RUNS));
+        let mut runs_left = runs.into_iter();
+        loop {
+            let group = runs_left.by_ref().take(MAX_MERGED_RUNS).collect::<Vec<_>>();
+            if group.len() < 2 {
+                merged_runs.extend(group);
+                break;
+            }
+            let merged = SpilledRun::create(&options.temp_dir)?;
+            merge::<T>(&group, &merged.path, options.memory_budget)?;
+            merged_runs.push(merged);
+        }
+        runs = merged_runs;
+    }
+    merge::<T>(&runs, output, options.memory_budget)
+}
+
+//Temp file of a sorted run, removed once dropped
+struct SpilledRun {
+    path: PathBuf,
+}
+
+impl SpilledRun {
+    fn create(temp_dir: &Path) -> io::Result<Self> {
+        static NEXT_RUN: AtomicUsize = AtomicUsize::new(0);
+        fs::create_dir_all(temp_dir)?;
+        let path = temp_dir.join(format!(
+            "radix_sort_run_{}_{}",
+            process::id(),
+            NEXT_RUN.fetch_add(1, Relaxed)
+        ));
+        Ok(Self { path })
+    }
+}
+
+impl Drop for SpilledRun {
+    fn drop(&mut self) {
+        //Nothing left to do about a temp file that cannot be removed
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+//Head of a run in the merge, the smallest element on top and earlier runs first among equal ones
+struct MergeHead<T> {
+    element: T,
+    run_index: usize,
+}
+
+impl<T: RadixDigits> Ord for MergeHead<T> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        KeyDigits
+            .compare(&other.element, &self.element, 0..T::NUMBER_OF_DIGITS)
+            .then(other.run_index.cmp(&self.run_index))
+    }
+}
+
+impl<T: RadixDigits> PartialOrd for MergeHead<T> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<T: RadixDigits> PartialEq for MergeHead<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl<T: RadixDigits> Eq for MergeHead<T> {}
+
+//Reads a sorted run in blocks
+struct RunReader<T> {
+    file: BufReader<File>,
+    block: Vec<T>,
+    position: usize,
+    block_len: usize,
+}
+
+impl<T: PlainData> RunReader<T> {
+    fn next(&mut self) -> io::Result<Option<T>> {
+        if self.position == self.block.len() {
+            read_elements(&mut self.file, &mut self.block, self.block_len)?;
+            self.position = 0;
+        }
+        let element = self.block.get(self.position).copied();
+        self.position += 1;
+        Ok(element)
+    }
+}
+
+fn merge<T>(runs: &[SpilledRun], output: &Path, memory_budget: usize) -> io::Result<()>
+where
+    T: RadixDigits + PlainData,
+{
+    //The budget is split between the readers of every run and the writer
+    let block_len = (memory_budget / (runs.len() + 1) / size_of::<T>().max(1)).max(1);
+    let mut readers = runs
+        .iter()
+        .map(|run| {
+            Ok(RunReader {
+                file: BufReader::new(File::open(&run.path)?),
+                block: Vec::<T>::with_capacity(block_len),
+                position: 0,
+                block_len,
+            })
+        })
+        .collect::<io::Result<Vec<_>>>()?;
+    let mut heads = BinaryHeap::with_capacity(readers.len());
+    for (run_index, reader) in readers.iter_mut().enumerate() {
+        if let Some(element) = reader.next()? {
+            heads.push(MergeHead { element, run_index });
+        }
+    }
+    let mut output = File::create(output)?;
+    let mut block = Vec::<T>::with_capacity(block_len);
+    while let Some(MergeHead { element, run_index }) = heads.pop() {
+        block.push(element);
+        if block.len() == block_len {
+            write_elements(&mut output, &block)?;
+            block.clear();
+        }
+        if let Some(element) = readers[run_index].next()? {
+            heads.push(MergeHead { element, run_index });
+        }
+    }
+    write_elements(&mut output, &block)
+}
+
+//Replaces the contents of `elements` with up to `len` elements, fewer only at the end of input
+pub(crate) fn read_elements<T: PlainData>(
+    input: &mut impl Read,
+    elements: &mut Vec<T>,
+    len: usize,
+) -> io::Result<()> {
+    elements.clear();
+    elements.reserve(len);
+    //Zeroed first, as reading into uninitialized bytes is undefined
+    let bytes = unsafe {
+        let bytes = elements.spare_capacity_mut().as_mut_ptr() as *mut u8;
+        ptr::write_bytes(bytes, 0, len * size_of::<T>());
+        slice::from_raw_parts_mut(bytes, len * size_of::<T>())
+    };
+    let mut filled = 0;
+    while filled < bytes.len() {
+        match input.read(&mut bytes[filled..]) {
+            Ok(0) => break,
+            Ok(read) => filled += read,
+            Err(error) if error.kind() == io::ErrorKind::Interrupted => {}
+            Err(error) => return Err(error),
+        }
+    }
+    if !filled.is_multiple_of(size_of::<T>().max(1)) {
+        return Err(io::Error::new(
+            io::ErrorKind::UnexpectedEof,
+            "input does not end on an element boundary",
+        ));
+    }
+    unsafe {
+        elements.set_len(filled / size_of::<T>().max(1));
+    }
+    Ok(())
+}
+
+fn write_file<T: PlainData>(path: &Path, elements: &[T]) -> io::Result<()> {
+    let mut output = BufWriter::new(File::create(path)?);
+    write_elements(&mut output, elements)?;
+    output.flush()
+}
+
+pub(crate) fn write_elements<T: PlainData>(
+    output: &mut impl Write,
+    elements: &[T],
+) -> io::Result<()> {
+    let bytes =
+        unsafe { slice::from_raw_parts(elements.as_ptr() as *const u8, size_of_val(elements)) };
+    output.write_all(bytes)
+}
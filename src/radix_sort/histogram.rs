@@ -0,0 +1,289 @@
+//Counters of neighbouring elements go to separate tables, so that runs of equal digits
+//do not wait on the store of the previous increment
+const LANES: usize = 4;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum KeyTransform {
+    Unsigned,
+    //Sign bit flipped
+    Signed,
+    //All bits flipped on negative values, sign bit flipped on positive ones
+    Float,
+}
+
+impl KeyTransform {
+    fn apply_32(self, bits: u32) -> u32 {
+        match self {
+            KeyTransform::Unsigned => bits,
+            KeyTransform::Signed => bits ^ 1 << 31,
+            KeyTransform::Float => bits ^ (((bits as i32) >> 31) as u32 | 1 << 31),
+        }
+    }
+
+    fn apply_64(self, bits: u64) -> u64 {
+        match self {
+            KeyTransform::Unsigned => bits,
+            KeyTransform::Signed => bits ^ 1 << 63,
+            KeyTransform::Float => bits ^ (((bits as i64) >> 63) as u64 | 1 << 63),
+        }
+    }
+}
+
+pub(crate) fn multi_histogram<T>(elements: &[T], digit: impl Fn(&T) -> u8) -> [usize; 256] {
+    let mut bin_histogram = [0; 256];
+    for block in elements.chunks(u32::MAX as usize) {
+        let mut lane_histograms = [[0u32; 256]; LANES];
+        let mut groups = block.chunks_exact(LANES);
+        for group in &mut groups {
+            for (lane_histogram, element) in lane_histograms.iter_mut().zip(group) {
+                lane_histogram[digit(element) as usize] += 1;
+            }
+        }
+        for element in groups.remainder() {
+            lane_histograms[0][digit(element) as usize] += 1;
+        }
+        for lane_histogram in &lane_histograms {
+            for (bin_count, lane_count) in bin_histogram.iter_mut().zip(lane_histogram) {
+                *bin_count += *lane_count as usize;
+            }
+        }
+    }
+    bin_histogram
+}
+
+//Histogram of a digit of 4 byte keys stored as their bits
+pub(crate) fn histogram_32<T>(elements: &[T], index: u8, transform: KeyTransform) -> [usize; 256] {
+    assert_eq!(size_of::<T>(), size_of::<u32>());
+    unsafe { field_histogram_32(elements, 0, index, transform) }
+}
+
+//Histogram of a digit of 8 byte keys stored as their bits
+pub(crate) fn histogram_64<T>(elements: &[T], index: u8, transform: KeyTransform) -> [usize; 256] {
+    assert_eq!(size_of::<T>(), size_of::<u64>());
+    unsafe { field_histogram_64(elements, 0, index, transform) }
+}
+
+//Same as `histogram_32` for keys at `offset` bytes within every element, which has to hold the
+//bits of a 4 byte key there
+pub(crate) unsafe fn field_histogram_32<E>(
+    elements: &[E],
+    offset: usize,
+    index: u8,
+    transform: KeyTransform,
+) -> [usize; 256] {
+    #[cfg(target_arch = "x86_64")]
+    if is_x86_feature_detected!("avx2") {
+        return histogram_32_avx2(elements, offset, index, transform);
+    }
+    multi_histogram(elements, |element| {
+        let key = (element as *const E)
+            .byte_add(offset)
+            .cast::<u32>()
+            .read_unaligned();
+        (transform.apply_32(key) >> (index * 8)) as u8
+    })
+}
+
+//Same as `histogram_64` for keys at `offset` bytes within every element, which has to hold the
+//bits of an 8 byte key there
+pub(crate) unsafe fn field_histogram_64<E>(
+    elements: &[E],
+    offset: usize,
+    index: u8,
+    transform: KeyTransform,
+) -> [usize; 256] {
+    #[cfg(target_arch = "x86_64")]
+    if is_x86_feature_detected!("avx2") {
+        return histogram_64_avx2(elements, offset, index, transform);
+    }
+    multi_histogram(elements, |element| {
+        let key = (element as *const E)
+            .byte_add(offset)
+            .cast::<u64>()
+            .read_unaligned();
+        (transform.apply_64(key) >> (index * 8)) as u8
+    })
+}
+
+//How the keys of a group of elements are brought into one vector, in any order
+#[cfg(target_arch = "x86_64")]
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum KeyLoad {
+    //Elements that are nothing but their key
+    Contiguous,
+    //Elements that are pairs of keys of the same size, the key in the low or high half
+    PairLow,
+    PairHigh,
+    //Elements of any other size
+    Gather,
+}
+
+#[cfg(target_arch = "x86_64")]
+impl KeyLoad {
+    fn of<E>(key_size: usize, offset: usize) -> Self {
+        if size_of::<E>() == key_size {
+            KeyLoad::Contiguous
+        } else if size_of::<E>() == 2 * key_size && offset == 0 {
+            KeyLoad::PairLow
+        } else if size_of::<E>() == 2 * key_size && offset == key_size {
+            KeyLoad::PairHigh
+        } else {
+            KeyLoad::Gather
+        }
+    }
+}
+
+#[cfg(target_arch = "x86_64")]
+#[target_feature(enable = "avx2")]
+unsafe fn histogram_32_avx2<E>(
+    elements: &[E],
+    offset: usize,
+    index: u8,
+    transform: KeyTransform,
+) -> [usize; 256] {
+    use std::arch::x86_64::*;
+    let key = |element: &E| {
+        (element as *const E)
+            .byte_add(offset)
+            .cast::<u32>()
+            .read_unaligned()
+    };
+    //Byte offsets of the keys of a group gathered at once have to fit an i32
+    let key_load = KeyLoad::of::<E>(size_of::<u32>(), offset);
+    if key_load == KeyLoad::Gather && size_of::<E>().saturating_mul(8) > i32::MAX as usize {
+        return multi_histogram(elements, |element| {
+            (transform.apply_32(key(element)) >> (index * 8)) as u8
+        });
+    }
+    let gather_offsets = _mm256_setr_epi32(0, 1, 2, 3, 4, 5, 6, 7);
+    let gather_offsets = _mm256_add_epi32(
+        _mm256_mullo_epi32(gather_offsets, _mm256_set1_epi32(size_of::<E>() as i32)),
+        _mm256_set1_epi32(offset as i32),
+    );
+    let mut bin_histogram = [0; 256];
+    let sign_bit = _mm256_set1_epi32(i32::MIN);
+    let digit_mask = _mm256_set1_epi32(0xFF);
+    let shift = _mm_cvtsi32_si128(index as i32 * 8);
+    for block in elements.chunks(u32::MAX as usize) {
+        let mut lane_histograms = [[0u32; 256]; 8];
+        let mut groups = block.chunks_exact(8);
+        for group in &mut groups {
+            let group_ptr = group.as_ptr() as *const u8;
+            let mut bits = match key_load {
+                KeyLoad::Contiguous => _mm256_loadu_si256(group_ptr as *const __m256i),
+                KeyLoad::PairLow | KeyLoad::PairHigh => {
+                    let first =
+                        _mm256_castsi256_ps(_mm256_loadu_si256(group_ptr as *const __m256i));
+                    let second = _mm256_castsi256_ps(_mm256_loadu_si256(
+                        group_ptr.add(32) as *const __m256i
+                    ));
+                    _mm256_castps_si256(if key_load == KeyLoad::PairLow {
+                        _mm256_shuffle_ps::<0b10_00_10_00>(first, second)
+                    } else {
+                        _mm256_shuffle_ps::<0b11_01_11_01>(first, second)
+                    })
+                }
+                KeyLoad::Gather => {
+                    _mm256_i32gather_epi32::<1>(group_ptr as *const i32, gather_offsets)
+                }
+            };
+            bits = match transform {
+                KeyTransform::Unsigned => bits,
+                KeyTransform::Signed => _mm256_xor_si256(bits, sign_bit),
+                KeyTransform::Float => {
+                    _mm256_xor_si256(bits, _mm256_or_si256(_mm256_srai_epi32(bits, 31), sign_bit))
+                }
+            };
+            let digits = _mm256_and_si256(_mm256_srl_epi32(bits, shift), digit_mask);
+            let mut digits_array = [0u32; 8];
+            _mm256_storeu_si256(digits_array.as_mut_ptr() as *mut __m256i, digits);
+            for (lane_histogram, digit) in lane_histograms.iter_mut().zip(digits_array) {
+                lane_histogram[digit as usize] += 1;
+            }
+        }
+        for element in groups.remainder() {
+            let digit = (transform.apply_32(key(element)) >> (index * 8)) as u8;
+            lane_histograms[0][digit as usize] += 1;
+        }
+        for lane_histogram in &lane_histograms {
+            for (bin_count, lane_count) in bin_histogram.iter_mut().zip(lane_histogram) {
+                *bin_count += *lane_count as usize;
+            }
+        }
+    }
+    bin_histogram
+}
+
+#[cfg(target_arch = "x86_64")]
+#[target_feature(enable = "avx2")]
+unsafe fn histogram_64_avx2<E>(
+    elements: &[E],
+    offset: usize,
+    index: u8,
+    transform: KeyTransform,
+) -> [usize; 256] {
+    use std::arch::x86_64::*;
+    let key = |element: &E| {
+        (element as *const E)
+            .byte_add(offset)
+            .cast::<u64>()
+            .read_unaligned()
+    };
+    let key_load = KeyLoad::of::<E>(size_of::<u64>(), offset);
+    let gather_offsets = _mm256_setr_epi64x(
+        offset as i64,
+        (size_of::<E>() + offset) as i64,
+        (2 * size_of::<E>() + offset) as i64,
+        (3 * size_of::<E>() + offset) as i64,
+    );
+    let mut bin_histogram = [0; 256];
+    let sign_bit = _mm256_set1_epi64x(i64::MIN);
+    let digit_mask = _mm256_set1_epi64x(0xFF);
+    let shift = _mm_cvtsi32_si128(index as i32 * 8);
+    for block in elements.chunks(u32::MAX as usize) {
+        let mut lane_histograms = [[0u32; 256]; 4];
+        let mut groups = block.chunks_exact(4);
+        for group in &mut groups {
+            let group_ptr = group.as_ptr() as *const u8;
+            let mut bits = match key_load {
+                KeyLoad::Contiguous => _mm256_loadu_si256(group_ptr as *const __m256i),
+                KeyLoad::PairLow | KeyLoad::PairHigh => {
+                    let first = _mm256_loadu_si256(group_ptr as *const __m256i);
+                    let second = _mm256_loadu_si256(group_ptr.add(32) as *const __m256i);
+                    if key_load == KeyLoad::PairLow {
+                        _mm256_unpacklo_epi64(first, second)
+                    } else {
+                        _mm256_unpackhi_epi64(first, second)
+                    }
+                }
+                KeyLoad::Gather => {
+                    _mm256_i64gather_epi64::<1>(group_ptr as *const i64, gather_offsets)
+                }
+            };
+            bits = match transform {
+                KeyTransform::Unsigned => bits,
+                KeyTransform::Signed => _mm256_xor_si256(bits, sign_bit),
+                KeyTransform::Float => _mm256_xor_si256(
+                    bits,
+                    _mm256_or_si256(_mm256_cmpgt_epi64(_mm256_setzero_si256(), bits), sign_bit),
+                ),
+            };
+            let digits = _mm256_and_si256(_mm256_srl_epi64(bits, shift), digit_mask);
+            let mut digits_array = [0u64; 4];
+            _mm256_storeu_si256(digits_array.as_mut_ptr() as *mut __m256i, digits);
+            for (lane_histogram, digit) in lane_histograms.iter_mut().zip(digits_array) {
+                lane_histogram[digit as usize] += 1;
+            }
+        }
+        for element in groups.remainder() {
+            let digit = (transform.apply_64(key(element)) >> (index * 8)) as u8;
+            lane_histograms[0][digit as usize] += 1;
+        }
+        for lane_histogram in &lane_histograms {
+            for (bin_count, lane_count) in bin_histogram.iter_mut().zip(lane_histogram) {
+                *bin_count += *lane_count as usize;
+            }
+        }
+    }
+    bin_histogram
+}
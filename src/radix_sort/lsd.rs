@@ -0,0 +1,507 @@
+use super::{
+    counting::counting_sort_chunks,
+    histogram::multi_histogram,
+    permutation::permute_in_place,
+    prefix_sum::HistogramScan,
+    runner::{ChunkRunner, Chunks},
+    scratch::{prefault_pages, TempBuffer},
+    stream::{AlignedBuffer, FlushKind, CACHE_LINE},
+    RadixDigits, RadixSortOptions,
+};
+use std::{
+    cmp::Ordering,
+    mem::MaybeUninit,
+    ops::Range,
+    ptr::{self, copy_nonoverlapping},
+    slice,
+};
+
+//Digits the passes of a sort are made over
+pub(crate) trait PassDigits<T>: Sync {
+    fn digit(&self, element: &T, index: u8) -> u8;
+
+    fn histogram(&self, elements: &[T], index: u8) -> [usize; 256] {
+        multi_histogram(elements, |element| self.digit(element, index))
+    }
+
+    //Order of the keys made of the given digits, most significant digit last
+    fn compare(&self, a: &T, b: &T, digit_indices: Range<u8>) -> Ordering {
+        digit_indices
+            .rev()
+            .map(|index| self.digit(a, index).cmp(&self.digit(b, index)))
+            .find(|ordering| ordering.is_ne())
+            .unwrap_or(Ordering::Equal)
+    }
+}
+
+//Digits of the key itself
+pub(crate) struct KeyDigits;
+
+impl<T: RadixDigits> PassDigits<T> for KeyDigits {
+    fn digit(&self, element: &T, index: u8) -> u8 {
+        element.get_digit(index)
+    }
+
+    fn histogram(&self, elements: &[T], index: u8) -> [usize; 256] {
+        T::histogram(elements, index)
+    }
+
+    fn compare(&self, a: &T, b: &T, digit_indices: Range<u8>) -> Ordering {
+        if T::NUMBER_OF_DIGITS <= 16 && digit_indices == (0..T::NUMBER_OF_DIGITS) {
+            a.radix_key().cmp(&b.radix_key())
+        } else {
+            digit_indices
+                .rev()
+                .map(|index| a.get_digit(index).cmp(&b.get_digit(index)))
+                .find(|ordering| ordering.is_ne())
+                .unwrap_or(Ordering::Equal)
+        }
+    }
+}
+
+//Digits of the distance of the key from the smallest key
+pub(crate) struct OffsetKeyDigits {
+    pub(crate) min_key: u128,
+}
+
+impl<T: RadixDigits> PassDigits<T> for OffsetKeyDigits {
+    fn digit(&self, element: &T, index: u8) -> u8 {
+        ((element.radix_key() - self.min_key) >> (index * 8)) as u8
+    }
+
+    fn compare(&self, a: &T, b: &T, _digit_indices: Range<u8>) -> Ordering {
+        a.radix_key().cmp(&b.radix_key())
+    }
+}
+
+//Digits of the bits of the key within a range, lowest first
+pub(crate) struct BitRangeDigits {
+    pub(crate) bits: Range<u32>,
+}
+
+impl<T: RadixDigits> PassDigits<T> for BitRangeDigits {
+    fn digit(&self, element: &T, index: u8) -> u8 {
+        let first_bit = self.bits.start + index as u32 * 8;
+        let key_digit_index = (first_bit / 8) as u8;
+        let shift = first_bit % 8;
+        let mut digit = element.get_digit(key_digit_index) >> shift;
+        if shift > 0 && key_digit_index + 1 < T::NUMBER_OF_DIGITS {
+            digit |= element.get_digit(key_digit_index + 1) << (8 - shift);
+        }
+        let width = (self.bits.end - first_bit).min(8);
+        digit & (u8::MAX >> (8 - width))
+    }
+}
+
+pub(crate) fn sort_keys<T: RadixDigits>(
+    data: &mut [T],
+    options: &RadixSortOptions,
+    runner: ChunkRunner,
+) {
+    if data.len() < 2 {
+        return;
+    }
+    let chunks = Chunks::new(data.len(), options, runner);
+    //Large elements are moved once, after their keys have been sorted on their own
+    if size_of::<T>() >= options.indirect_threshold
+        && T::NUMBER_OF_DIGITS <= 16
+        && u32::try_from(data.len()).is_ok()
+    {
+        match T::NUMBER_OF_DIGITS {
+            0..=4 => sort_indirect::<T, u32>(data, options, &chunks),
+            5..=8 => sort_indirect::<T, u64>(data, options, &chunks),
+            _ => sort_indirect::<T, u128>(data, options, &chunks),
+        }
+    } else {
+        sort_direct(data, options, &chunks);
+    }
+}
+
+pub(crate) fn sort_direct<T: RadixDigits>(
+    data: &mut [T],
+    options: &RadixSortOptions,
+    chunks: &Chunks,
+) {
+    //Counting pays off once there are as many elements as keys
+    if T::KEY_DOMAIN.is_some_and(|domain| domain <= data.len()) {
+        counting_sort_chunks(data, chunks);
+    } else if options.key_range && T::NUMBER_OF_DIGITS <= 16 {
+        let (min_key, max_key) = key_range(data, chunks);
+        let number_of_digits = (u128::BITS - (max_key - min_key).leading_zeros()).div_ceil(8);
+        lsd_sort(
+            data,
+            options,
+            chunks,
+            &OffsetKeyDigits { min_key },
+            0..number_of_digits as u8,
+        );
+    } else {
+        lsd_sort(data, options, chunks, &KeyDigits, 0..T::NUMBER_OF_DIGITS);
+    }
+}
+
+//Sorts (key, index) pairs, with keys of the smallest type holding all digits of T, and moves
+//every element into place without temp memory for the elements
+fn sort_indirect<T, K>(data: &mut [T], options: &RadixSortOptions, chunks: &Chunks)
+where
+    T: RadixDigits,
+    K: RadixDigits + TryFrom<u128>,
+{
+    let mut pairs = Vec::<(K, u32)>::with_capacity(data.len());
+    let pairs_slice = unsafe { slice::from_raw_parts(pairs.as_ptr(), data.len()) };
+    chunks.run(|chunk_index| {
+        for index in chunks.range(chunk_index) {
+            let key = K::try_from(data[index].radix_key())
+                .unwrap_or_else(|_| unreachable!("key wider than its digits"));
+            unsafe {
+                (&pairs_slice[index] as *const (K, u32) as *mut (K, u32))
+                    .write((key, index as u32));
+            }
+        }
+    });
+    unsafe {
+        pairs.set_len(data.len());
+    }
+    sort_direct(&mut pairs, options, chunks);
+    permute_in_place(data, chunks, |index| pairs[index].1 as usize);
+}
+
+pub(crate) fn sort_bits<T: RadixDigits>(
+    data: &mut [T],
+    options: &RadixSortOptions,
+    runner: ChunkRunner,
+    bits: Range<u32>,
+) {
+    assert!(
+        bits.start <= bits.end && bits.end <= T::NUMBER_OF_DIGITS as u32 * 8,
+        "bit range {bits:?} out of the key"
+    );
+    if data.len() < 2 {
+        return;
+    }
+    let number_of_digits = (bits.end - bits.start).div_ceil(8) as u8;
+    lsd_sort(
+        data,
+        options,
+        &Chunks::new(data.len(), options, runner),
+        &BitRangeDigits { bits },
+        0..number_of_digits,
+    );
+}
+
+fn key_range<T: RadixDigits>(data: &[T], chunks: &Chunks) -> (u128, u128) {
+    chunks
+        .run(|chunk_index| {
+            data[chunks.range(chunk_index)].iter().fold(
+                (u128::MAX, u128::MIN),
+                |(min_key, max_key), element| {
+                    let key = element.radix_key();
+                    (min_key.min(key), max_key.max(key))
+                },
+            )
+        })
+        .into_iter()
+        .fold((u128::MAX, u128::MIN), |(min_key, max_key), chunk_range| {
+            (min_key.min(chunk_range.0), max_key.max(chunk_range.1))
+        })
+}
+
+//Bytes of the blocks of a chunk checked for order and counted one after the other, within L2
+const ORDER_BLOCK_BYTES: usize = 1 << 16;
+
+//Whether keys never decrease and whether they strictly decrease
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct InputOrder {
+    ascending: bool,
+    strictly_descending: bool,
+}
+
+impl InputOrder {
+    fn of_pair(ordering: Ordering) -> Self {
+        Self {
+            ascending: ordering.is_le(),
+            strictly_descending: ordering.is_gt(),
+        }
+    }
+
+    fn and(self, other: Self) -> Self {
+        Self {
+            ascending: self.ascending && other.ascending,
+            strictly_descending: self.strictly_descending && other.strictly_descending,
+        }
+    }
+
+    fn is_monotonic(self) -> bool {
+        self.ascending || self.strictly_descending
+    }
+}
+
+fn input_order<T, D: PassDigits<T>>(
+    elements: &[T],
+    digits: &D,
+    digit_indices: &Range<u8>,
+) -> InputOrder {
+    let mut order = InputOrder {
+        ascending: true,
+        strictly_descending: true,
+    };
+    for pair in elements.windows(2) {
+        order = order.and(InputOrder::of_pair(digits.compare(
+            &pair[0],
+            &pair[1],
+            digit_indices.clone(),
+        )));
+        if !order.is_monotonic() {
+            break;
+        }
+    }
+    order
+}
+
+//Strictly descending keys are all distinct, so reversing them is stable
+fn reverse<T: RadixDigits>(data: &mut [T], chunks: &Chunks) {
+    let len = data.len();
+    let elements = &*data;
+    let half_len = len / 2;
+    let elements_per_chunk = half_len.div_ceil(chunks.number_of_chunks).max(1);
+    let half_chunks = Chunks {
+        len: half_len,
+        elements_per_chunk,
+        number_of_chunks: half_len.div_ceil(elements_per_chunk),
+        ..*chunks
+    };
+    half_chunks.run(|chunk_index| {
+        for index in half_chunks.range(chunk_index) {
+            unsafe {
+                ptr::swap(
+                    &elements[index] as *const T as *mut T,
+                    &elements[len - 1 - index] as *const T as *mut T,
+                );
+            }
+        }
+    });
+}
+
+fn new_temp<T: RadixDigits>(options: &RadixSortOptions, chunks: &Chunks) -> TempBuffer<T> {
+    let temp = TempBuffer::<T>::new(chunks.len);
+    if options.prefault {
+        let temp_slice = temp.as_slice();
+        chunks.run(|chunk_index| {
+            let temp_chunk = &temp_slice[chunks.range(chunk_index)];
+            prefault_pages(unsafe {
+                slice::from_raw_parts_mut(
+                    temp_chunk.as_ptr() as *mut MaybeUninit<T>,
+                    temp_chunk.len(),
+                )
+            });
+        });
+    }
+    temp
+}
+
+//Passes whose digit is the same for all elements are skipped,
+//temp memory is only allocated once a pass has to move elements
+pub(crate) fn lsd_sort<T, D>(
+    data: &mut [T],
+    options: &RadixSortOptions,
+    chunks: &Chunks,
+    digits: &D,
+    digit_indices: Range<u8>,
+) where
+    T: RadixDigits,
+    D: PassDigits<T>,
+{
+    let buffer_len = options.buffer_len::<T>();
+    let aligned_flushes = size_of::<T>() > 0 && CACHE_LINE.is_multiple_of(size_of::<T>());
+    let flush_kind = FlushKind::detect(options.streaming_stores);
+    let mut temp: Option<TempBuffer<T>> = None;
+    let mut sorted_in_temp = false;
+    let first_digit_index = digit_indices.start;
+    for current_digit_index in digit_indices.clone() {
+        let (bin_histogram_per_chunk, bin_starts_per_chunk) = {
+            let src = match &temp {
+                Some(temp) if sorted_in_temp => temp.as_slice(),
+                _ => &*data,
+            };
+            //The order of the input is checked on the chunks read for the first histogram
+            let check_order = current_digit_index == first_digit_index;
+            let scan = HistogramScan::new(chunks.number_of_chunks);
+            let chunk_orders = chunks.run(|chunk_index| {
+                let src_chunk = &src[chunks.range(chunk_index)];
+                if !check_order {
+                    scan.add(
+                        chunk_index,
+                        digits.histogram(src_chunk, current_digit_index),
+                    );
+                    return None;
+                }
+                //Every block is checked right before it is counted, while it is in cache, so
+                //that nearly sorted input is read from memory once
+                let mut order = InputOrder {
+                    ascending: true,
+                    strictly_descending: true,
+                };
+                let mut bin_histogram = [0; 256];
+                let mut previous = None;
+                //Elements larger than a block are checked one by one
+                let block_len = (ORDER_BLOCK_BYTES / size_of::<T>().max(1)).max(1);
+                for block in src_chunk.chunks(block_len) {
+                    if order.is_monotonic() {
+                        if let Some(previous) = previous {
+                            order = order.and(InputOrder::of_pair(digits.compare(
+                                previous,
+                                &block[0],
+                                digit_indices.clone(),
+                            )));
+                        }
+                        order = order.and(input_order(block, digits, &digit_indices));
+                    }
+                    previous = block.last();
+                    let block_histogram = digits.histogram(block, current_digit_index);
+                    for (bin_count, block_count) in bin_histogram.iter_mut().zip(block_histogram) {
+                        *bin_count += block_count;
+                    }
+                }
+                scan.add(chunk_index, bin_histogram);
+                Some(order)
+            });
+            if check_order {
+                let order = (1..chunks.number_of_chunks)
+                    .map(|chunk_index| {
+                        let boundary = chunks.range(chunk_index).start;
+                        InputOrder::of_pair(digits.compare(
+                            &src[boundary - 1],
+                            &src[boundary],
+                            digit_indices.clone(),
+                        ))
+                    })
+                    .chain(chunk_orders.into_iter().flatten())
+                    .reduce(InputOrder::and)
+                    .expect("at least one chunk");
+                if order.ascending {
+                    return;
+                }
+                if order.strictly_descending {
+                    return reverse(data, chunks);
+                }
+            }
+            scan.finish()
+        };
+        if bin_starts_per_chunk.single_bin() {
+            continue;
+        }
+        let temp_slice = temp
+            .get_or_insert_with(|| new_temp(options, chunks))
+            .as_slice();
+        let (src, dst) = if sorted_in_temp {
+            (temp_slice, &*data)
+        } else {
+            (&*data, temp_slice)
+        };
+        chunks.run(|chunk_index| {
+            let src_chunk = &src[chunks.range(chunk_index)];
+            let mut bin_starts = bin_starts_per_chunk.for_chunk(chunk_index);
+            let bin_histogram = &bin_histogram_per_chunk[chunk_index];
+            //Elements of a bin holding nearly all of the chunk skip the buffers and are written
+            //in runs, shorter runs of a less dominant bin cost more than the buffers save
+            let dominant_digit = (0..256)
+                .max_by_key(|&digit| bin_histogram[digit])
+                .filter(|&digit| {
+                    options.dominant_runs && bin_histogram[digit] * 10 > src_chunk.len() * 9
+                })
+                .unwrap_or(256);
+            let write_run = |run: Range<usize>, bin_start: &mut usize| {
+                if !run.is_empty() {
+                    unsafe {
+                        flush_kind.flush(
+                            src_chunk[run.clone()].as_ptr(),
+                            &dst[*bin_start] as *const T as *mut T,
+                            run.len(),
+                        );
+                    }
+                    *bin_start += run.len();
+                }
+            };
+            if dominant_digit < 256 && bin_histogram[dominant_digit] == src_chunk.len() {
+                write_run(0..src_chunk.len(), &mut bin_starts[dominant_digit]);
+                flush_kind.fence();
+                return;
+            }
+            let mut derand_buffers = AlignedBuffer::<T>::new(256 * buffer_len);
+            let derand_buffers_ptr = derand_buffers.as_mut_ptr();
+            //Buffers are filled from the offset of their bin within a cache line,
+            //so that every flush after the first one writes whole lines
+            let mut derand_buffer_starts = [0; 256];
+            if aligned_flushes {
+                for digit in 0..256 {
+                    let misalignment =
+                        dst.as_ptr().wrapping_add(bin_starts[digit]) as usize % CACHE_LINE;
+                    if misalignment.is_multiple_of(size_of::<T>()) {
+                        derand_buffer_starts[digit] = misalignment / size_of::<T>();
+                    }
+                }
+            }
+            let mut derand_buffer_sizes = derand_buffer_starts;
+            let mut buffer = |element: &T, digit_value: usize, bin_starts: &mut [usize; 256]| {
+                let derand_buffer = unsafe { derand_buffers_ptr.add(digit_value * buffer_len) };
+                unsafe {
+                    copy_nonoverlapping(
+                        element,
+                        derand_buffer.add(derand_buffer_sizes[digit_value]),
+                        1,
+                    );
+                }
+                derand_buffer_sizes[digit_value] += 1;
+                if derand_buffer_sizes[digit_value] == buffer_len {
+                    let derand_buffer_start = derand_buffer_starts[digit_value];
+                    unsafe {
+                        flush_kind.flush(
+                            derand_buffer.add(derand_buffer_start),
+                            &dst[bin_starts[digit_value]] as *const T as *mut T,
+                            buffer_len - derand_buffer_start,
+                        );
+                    }
+                    bin_starts[digit_value] += buffer_len - derand_buffer_start;
+                    derand_buffer_starts[digit_value] = 0;
+                    derand_buffer_sizes[digit_value] = 0;
+                }
+            };
+            if dominant_digit < 256 {
+                let mut run_start = 0;
+                for (position, element) in src_chunk.iter().enumerate() {
+                    let digit_value = digits.digit(element, current_digit_index) as usize;
+                    if digit_value != dominant_digit {
+                        write_run(run_start..position, &mut bin_starts[dominant_digit]);
+                        run_start = position + 1;
+                        buffer(element, digit_value, &mut bin_starts);
+                    }
+                }
+                write_run(run_start..src_chunk.len(), &mut bin_starts[dominant_digit]);
+            } else {
+                for element in src_chunk {
+                    let digit_value = digits.digit(element, current_digit_index) as usize;
+                    buffer(element, digit_value, &mut bin_starts);
+                }
+            }
+            for digit in 0..256 {
+                if derand_buffer_sizes[digit] > derand_buffer_starts[digit] {
+                    unsafe {
+                        flush_kind.flush(
+                            derand_buffers_ptr
+                                .add(digit * buffer_len + derand_buffer_starts[digit]),
+                            &dst[bin_starts[digit]] as *const T as *mut T,
+                            derand_buffer_sizes[digit] - derand_buffer_starts[digit],
+                        );
+                    }
+                }
+            }
+            flush_kind.fence();
+        });
+        sorted_in_temp = !sorted_in_temp;
+    }
+    if let Some(temp) = temp.filter(|_| sorted_in_temp) {
+        unsafe {
+            copy_nonoverlapping(temp.as_slice().as_ptr(), data.as_mut_ptr(), data.len());
+        }
+    }
+}
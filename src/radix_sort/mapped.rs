@@ -0,0 +1,140 @@
+use super::{PlainData, RadixDigits, RadixSort};
+use std::{io, path::Path};
+
+//Sorts a file of elements of T stored back to back in place, through a shared mapping of the
+//file on Linux so it only has to fit in RAM and swap, plus the temp memory of the sort. Any bytes
+//of the file are taken as elements, so T is plain data.
+pub fn sort_file_in_place<T>(path: &Path) -> io::Result<()>
+where
+    T: RadixDigits + PlainData,
+{
+    sort_file_in_place_at::<T>(path, 0)
+}
+
+//Same as `sort_file_in_place` for the elements following the first `offset` bytes of the file,
+//which is a multiple of the alignment of T
+pub fn sort_file_in_place_at<T>(path: &Path, offset: usize) -> io::Result<()>
+where
+    T: RadixDigits + PlainData,
+{
+    if !offset.is_multiple_of(align_of::<T>()) {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "offset not aligned for the elements",
+        ));
+    }
+    #[cfg(target_os = "linux")]
+    {
+        let mut file = MappedFile::open(path)?;
+        file.as_mut_slice::<T>(offset)?.radix_sort();
+        file.sync()
+    }
+    #[cfg(not(target_os = "linux"))]
+    {
+        use super::external::{read_elements, write_elements};
+        use std::{
+            fs::OpenOptions,
+            io::{Read, Seek, SeekFrom},
+        };
+        let mut file = OpenOptions::new().read(true).write(true).open(path)?;
+        let file_len = file.metadata()?.len() as usize;
+        let data_len = file_len.checked_sub(offset).ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                "offset past the end of the file",
+            )
+        })?;
+        file.seek(SeekFrom::Start(offset as u64))?;
+        let mut elements = Vec::new();
+        read_elements(&mut file, &mut elements, data_len / size_of::<T>().max(1))?;
+        if file.read(&mut [0])? != 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "file does not end on an element boundary",
+            ));
+        }
+        elements.radix_sort();
+        file.seek(SeekFrom::Start(offset as u64))?;
+        write_elements(&mut file, &elements)?;
+        file.sync_data()
+    }
+}
+
+//Shared read-write mapping of a whole file, unmapped once dropped
+#[cfg(target_os = "linux")]
+struct MappedFile {
+    ptr: *mut libc::c_void,
+    len: usize,
+}
+
+#[cfg(target_os = "linux")]
+impl MappedFile {
+    fn open(path: &Path) -> io::Result<Self> {
+        use std::{fs::OpenOptions, os::fd::AsRawFd, ptr};
+        let file = OpenOptions::new().read(true).write(true).open(path)?;
+        let len = usize::try_from(file.metadata()?.len())
+            .map_err(|_| io::Error::new(io::ErrorKind::InvalidData, "file too large to map"))?;
+        if len == 0 {
+            //Empty mappings are rejected by mmap
+            return Ok(Self {
+                ptr: ptr::null_mut(),
+                len,
+            });
+        }
+        //The mapping stays valid after `file` is closed
+        let ptr = unsafe {
+            libc::mmap(
+                ptr::null_mut(),
+                len,
+                libc::PROT_READ | libc::PROT_WRITE,
+                libc::MAP_SHARED,
+                file.as_raw_fd(),
+                0,
+            )
+        };
+        if ptr == libc::MAP_FAILED {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(Self { ptr, len })
+    }
+
+    //Mappings start on a page boundary, so elements at an aligned offset are aligned, and any
+    //bytes are valid elements of plain data
+    fn as_mut_slice<T: PlainData>(&mut self, offset: usize) -> io::Result<&mut [T]> {
+        let Some(data_len) = self.len.checked_sub(offset) else {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "offset past the end of the file",
+            ));
+        };
+        if !data_len.is_multiple_of(size_of::<T>().max(1)) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "file does not end on an element boundary",
+            ));
+        }
+        if data_len == 0 {
+            return Ok(&mut []);
+        }
+        let len = data_len / size_of::<T>().max(1);
+        Ok(unsafe { std::slice::from_raw_parts_mut(self.ptr.byte_add(offset) as *mut T, len) })
+    }
+
+    fn sync(&self) -> io::Result<()> {
+        if self.len > 0 && unsafe { libc::msync(self.ptr, self.len, libc::MS_SYNC) } != 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(())
+    }
+}
+
+#[cfg(target_os = "linux")]
+impl Drop for MappedFile {
+    fn drop(&mut self) {
+        if self.len > 0 {
+            unsafe {
+                libc::munmap(self.ptr, self.len);
+            }
+        }
+    }
+}
@@ -1,22 +1,60 @@
+use counting::counting_sort_chunks;
+pub use external::{external_sort, ExternalSortOptions};
+use lsd::{lsd_sort, sort_bits, sort_keys, KeyDigits};
+pub use mapped::{sort_file_in_place, sort_file_in_place_at};
+pub use options::RadixSortOptions;
+pub use permutation::{
+    apply_inverse_permutation, apply_permutation, invert_permutation, Permutation,
+};
+pub use plain_data::PlainData;
+use prefix_sum::BinStartsPerChunk;
 pub use radix_digits::RadixDigits;
 use rayon::{
     current_num_threads,
-    iter::{IndexedParallelIterator, ParallelIterator},
-    slice::ParallelSlice,
+    iter::{IndexedParallelIterator, IntoParallelIterator, ParallelIterator},
+    slice::{ParallelSlice, ParallelSliceMut},
+    ThreadPool,
 };
+pub use records::sort_records;
+use runner::{ChunkRunner, Chunks};
+use scratch::{prefault_pages, TempBuffer};
 use std::{
-    mem::{transmute, MaybeUninit},
+    array,
+    mem::{self, MaybeUninit},
+    ops::Range,
     ptr::copy_nonoverlapping,
     slice::{self},
+    sync::atomic::{AtomicUsize, Ordering::Relaxed},
     thread::{self, available_parallelism},
 };
+pub use strategy::{CostModel, Strategy, StrategyCost};
 
+mod counting;
+mod external;
+mod histogram;
+mod lsd;
+mod mapped;
+mod numa;
+mod options;
+mod permutation;
+mod plain_data;
+mod prefix_sum;
 mod radix_digits;
+mod records;
+mod runner;
+mod scratch;
+mod strategy;
+mod stream;
 #[cfg(test)]
 mod tests;
 
 pub trait RadixSort<T: RadixDigits> {
     fn radix_sort(&mut self);
+    //Runs on the given pool instead of the current one
+    fn radix_sort_in(&mut self, pool: &ThreadPool);
+    fn radix_sort_with(&mut self, options: &RadixSortOptions);
+    //Orders only by the given bits of the key, counted from the lowest bit of digit 0
+    fn radix_sort_bits(&mut self, bits: Range<u32>);
 }
 
 pub trait RadixSortCopyOnly<T>
@@ -35,178 +73,82 @@ where
     fn radix_sort4(&mut self);
     //Buffering of writes
     fn radix_sort5(&mut self);
+    //Histogram of the next digit counted while scattering
+    fn radix_sort6(&mut self);
+    //Partitioning on the top digit, then buckets sorted on their own
+    fn radix_sort7(&mut self);
+    fn radix_sort_strategy(&mut self, strategy: Strategy);
 }
 
-pub fn counting_sort(data: &mut [u8]) {
-    let mut temp = vec![0; data.len()];
-    let mut bin_histogram = [0; 256];
-    for element in data.as_ref() {
-        bin_histogram[*element as usize] += 1;
+//Sorts keys with a small domain by counting them, keys without a domain with `radix_sort`
+pub fn counting_sort<T: RadixDigits>(data: &mut [T]) {
+    if data.len() < 2 {
+        return;
     }
-    let mut bin_starts = {
-        bin_histogram.iter_mut().fold(0, |bin_start, bin_count| {
-            let next_bin_start = bin_start + *bin_count;
-            *bin_count = bin_start;
-            next_bin_start
-        });
-        bin_histogram
-    };
-    for element in data.as_ref() {
-        temp[bin_starts[*element as usize]] = *element;
-        bin_starts[*element as usize] += 1;
+    if T::KEY_DOMAIN.is_none() {
+        return data.radix_sort();
     }
-    data.copy_from_slice(&temp);
+    let options = RadixSortOptions::from_env_cached();
+    counting_sort_chunks(data, &Chunks::new(data.len(), options, ChunkRunner::Pool));
 }
 
 impl<T: RadixDigits> RadixSort<T> for [T] {
     fn radix_sort(&mut self) {
-        const NUMBER_OF_THREADS: usize = 10;
-        const BUFFER_SIZE: usize = 96;
-        const PAGE_SIZE: usize = 4096;
-        let elements_per_cpu = self.len().div_ceil(
-            available_parallelism()
-                .expect("failed to acquire number of CPUs")
-                .get(),
-        );
-        let mut temp: Vec<MaybeUninit<T>> = Vec::with_capacity(self.len());
-        unsafe {
-            temp.set_len(self.len());
-            let temp_as_bytes = slice::from_raw_parts_mut(
-                temp.as_mut_ptr() as *mut u8,
-                self.len() * size_of::<T>(),
-            );
-            temp_as_bytes
-                .iter_mut()
-                .step_by(PAGE_SIZE)
-                .for_each(|element| *element = 0);
-        }
-        let temp_slice = unsafe { transmute(temp.as_slice()) };
-        for current_digit_index in 0..T::NUMBER_OF_DIGITS {
-            let (src, dst) = if current_digit_index % 2 == 0 {
-                (self.as_ref(), temp_slice)
-            } else {
-                (temp_slice, self.as_ref())
-            };
-            let mut bin_histogram_per_chunk = thread::scope(|scope| {
-                let workers = src
-                    .chunks(elements_per_cpu)
-                    .map(|src_chunk| {
-                        scope.spawn(move || {
-                            let mut bin_histogram = [0; 256];
-                            for element in src_chunk {
-                                bin_histogram[element.get_digit(current_digit_index) as usize] += 1;
-                            }
-                            bin_histogram
-                        })
-                    })
-                    .collect::<Vec<_>>();
-                workers
-                    .into_iter()
-                    .map(|thread_handle| {
-                        thread_handle.join().expect("failed to join worker thread")
-                    })
-                    .collect::<Vec<_>>()
-            });
-            let bin_starts_per_chunk = {
-                let mut prefix_sum = 0;
-                for digit in 0..256 {
-                    for bin_histogram in &mut bin_histogram_per_chunk {
-                        let new_prefix_sum = prefix_sum + bin_histogram[digit];
-                        bin_histogram[digit] = prefix_sum;
-                        prefix_sum = new_prefix_sum;
-                    }
-                }
-                bin_histogram_per_chunk
-            };
-            thread::scope(|scope| {
-                src.chunks(elements_per_cpu)
-                    .zip(bin_starts_per_chunk)
-                    .for_each(|(src_chunk, mut bin_starts)| {
-                        scope.spawn(move || {
-                            let mut derand_buffers =
-                                MaybeUninit::<[[T; BUFFER_SIZE]; 256]>::uninit();
-                            let derand_buffers_slice = unsafe { derand_buffers.assume_init_mut() };
-                            let mut derand_buffer_sizes = [0; 256];
-                            for element in src_chunk {
-                                let digit_value = element.get_digit(current_digit_index) as usize;
-                                unsafe {
-                                    copy_nonoverlapping(
-                                        element,
-                                        &derand_buffers_slice[digit_value]
-                                            [derand_buffer_sizes[digit_value]]
-                                            as *const T
-                                            as *mut T,
-                                        1,
-                                    );
-                                }
-                                derand_buffer_sizes[digit_value] += 1;
-                                if derand_buffer_sizes[digit_value] == BUFFER_SIZE {
-                                    unsafe {
-                                        copy_nonoverlapping(
-                                            derand_buffers_slice[digit_value].as_ptr(),
-                                            &dst[bin_starts[digit_value]] as *const T as *mut T,
-                                            BUFFER_SIZE,
-                                        );
-                                    }
-                                    bin_starts[digit_value] += BUFFER_SIZE;
-                                    derand_buffer_sizes[digit_value] = 0;
-                                }
-                            }
-                            for digit in 0..256 {
-                                if derand_buffer_sizes[digit] > 0 {
-                                    unsafe {
-                                        copy_nonoverlapping(
-                                            derand_buffers_slice[digit].as_ptr(),
-                                            &dst[bin_starts[digit]] as *const T as *mut T,
-                                            derand_buffer_sizes[digit],
-                                        );
-                                    }
-                                }
-                            }
-                        });
-                    });
-            });
-        }
-        if T::NUMBER_OF_DIGITS % 2 == 1 {
-            unsafe {
-                copy_nonoverlapping(temp_slice.as_ptr(), self.as_mut_ptr(), self.len());
-            }
+        self.radix_sort_with(RadixSortOptions::from_env_cached());
+    }
+
+    fn radix_sort_in(&mut self, pool: &ThreadPool) {
+        pool.install(|| self.radix_sort());
+    }
+
+    fn radix_sort_with(&mut self, options: &RadixSortOptions) {
+        with_runner(self.len(), options, |runner| {
+            sort_keys(self, options, runner)
+        });
+    }
+
+    fn radix_sort_bits(&mut self, bits: Range<u32>) {
+        let options = RadixSortOptions::from_env_cached();
+        with_runner(self.len(), options, |runner| {
+            sort_bits(self, options, runner, bits)
+        });
+    }
+}
+
+fn with_runner(len: usize, options: &RadixSortOptions, sort: impl FnOnce(ChunkRunner) + Send) {
+    if options.numa && len >= options.small_input_threshold {
+        if let Some(pool) = numa::pinned_pool() {
+            return pool.install(|| sort(ChunkRunner::Pinned));
         }
     }
+    sort(ChunkRunner::Pool);
 }
 
 impl<T> RadixSortCopyOnly<T> for [T]
 where
     T: RadixDigits + Default + Copy,
 {
+    fn radix_sort_strategy(&mut self, strategy: Strategy) {
+        if self.len() < 2 {
+            return;
+        }
+        match strategy.resolve::<T>(self.len()) {
+            Strategy::SingleThread => self.radix_sort0(),
+            Strategy::ThreadPerDigit => self.radix_sort1(),
+            Strategy::NativeThreads => self.radix_sort2(),
+            Strategy::Prefault => self.radix_sort3(),
+            Strategy::Rayon => self.radix_sort4(),
+            Strategy::Buffered => self.radix_sort5(),
+            Strategy::Pipelined => self.radix_sort6(),
+            Strategy::Hybrid => self.radix_sort7(),
+            Strategy::Auto => unreachable!("Auto resolves to a concrete strategy"),
+        }
+    }
+
     //Single thread
     fn radix_sort0(&mut self) {
         let mut temp = vec![T::default(); self.len()];
-        for current_digit_index in 0..T::NUMBER_OF_DIGITS {
-            let (src, dst) = if current_digit_index % 2 == 0 {
-                (self.as_ref(), temp.as_mut_slice())
-            } else {
-                (temp.as_slice(), self.as_mut())
-            };
-            let mut bin_histogram = [0; 256];
-            for element in src {
-                bin_histogram[element.get_digit(current_digit_index) as usize] += 1;
-            }
-            let mut bin_starts = {
-                bin_histogram.iter_mut().fold(0, |bin_start, bin_count| {
-                    let next_bin_start = bin_start + *bin_count;
-                    *bin_count = bin_start;
-                    next_bin_start
-                });
-                bin_histogram
-            };
-            for element in src {
-                let digit_value = element.get_digit(current_digit_index) as usize;
-                dst[bin_starts[digit_value]] = *element;
-                bin_starts[digit_value] += 1;
-            }
-        }
-        if T::NUMBER_OF_DIGITS % 2 == 1 {
+        if lsd_passes(self, &mut temp, 0..T::NUMBER_OF_DIGITS) {
             self.copy_from_slice(&temp);
         }
     }
@@ -215,7 +157,7 @@ where
     fn radix_sort1(&mut self) {
         let mut temp = vec![T::default(); self.len()];
         let bin_starts_per_digit = thread::scope(|scope| {
-            let data_ref = self.as_ref();
+            let data_ref = &*self;
             let workers = (0..T::NUMBER_OF_DIGITS)
                 .map(|digit_index| {
                     scope.spawn(move || {
@@ -239,9 +181,9 @@ where
         });
         for (digit_index, mut bin_starts) in bin_starts_per_digit.into_iter().enumerate() {
             let (src, dst) = if digit_index % 2 == 0 {
-                (self.as_ref(), temp.as_mut_slice())
+                (&*self, temp.as_mut_slice())
             } else {
-                (temp.as_slice(), self.as_mut())
+                (temp.as_slice(), &mut *self)
             };
             for element in src {
                 let digit_value = element.get_digit(digit_index as u8) as usize;
@@ -264,9 +206,9 @@ where
         let temp = vec![T::default(); self.len()];
         for current_digit_index in 0..T::NUMBER_OF_DIGITS {
             let (src, dst) = if current_digit_index % 2 == 0 {
-                (self.as_ref(), temp.as_slice())
+                (&*self, temp.as_slice())
             } else {
-                (temp.as_slice(), self.as_ref())
+                (temp.as_slice(), &*self)
             };
             let mut bin_histogram_per_chunk = thread::scope(|scope| {
                 let workers = src
@@ -305,8 +247,7 @@ where
                     .for_each(|(src_chunk, mut bin_starts)| {
                         scope.spawn(move || {
                             for element in src_chunk {
-                                let digit_value =
-                                    element.get_digit(current_digit_index as u8) as usize;
+                                let digit_value = element.get_digit(current_digit_index) as usize;
                                 let dst = unsafe {
                                     slice::from_raw_parts_mut(dst.as_ptr() as *mut T, dst.len())
                                 };
@@ -324,29 +265,23 @@ where
 
     //Partially initialized temp memory
     fn radix_sort3(&mut self) {
-        const PAGE_SIZE: usize = 4096;
         let elements_per_chunk = self.len().div_ceil(
             available_parallelism()
                 .expect("failed to acquire number of CPUs")
                 .get(),
         );
-        let mut temp = Vec::with_capacity(self.len());
-        unsafe {
-            temp.set_len(self.len());
-            let temp_as_bytes = slice::from_raw_parts_mut(
-                temp.as_mut_ptr() as *mut u8,
-                self.len() * size_of::<T>(),
-            );
-            temp_as_bytes
-                .iter_mut()
-                .step_by(PAGE_SIZE)
-                .for_each(|element| *element = 0);
-        }
+        let mut temp = TempBuffer::<T>::new(self.len());
+        thread::scope(|scope| {
+            for temp_chunk in temp.as_mut_slice().chunks_mut(elements_per_chunk) {
+                scope.spawn(|| prefault_pages(temp_chunk));
+            }
+        });
+        let temp_slice = temp.as_slice();
         for current_digit_index in 0..T::NUMBER_OF_DIGITS {
             let (src, dst) = if current_digit_index % 2 == 0 {
-                (self.as_ref(), temp.as_slice())
+                (&*self, temp_slice)
             } else {
-                (temp.as_slice(), self.as_ref())
+                (temp_slice, &*self)
             };
             let mut bin_histogram_per_chunk = thread::scope(|scope| {
                 let workers = src
@@ -385,8 +320,7 @@ where
                     .for_each(|(src_chunk, mut bin_starts)| {
                         scope.spawn(move || {
                             for element in src_chunk {
-                                let digit_value =
-                                    element.get_digit(current_digit_index as u8) as usize;
+                                let digit_value = element.get_digit(current_digit_index) as usize;
                                 let dst = unsafe {
                                     slice::from_raw_parts_mut(dst.as_ptr() as *mut T, dst.len())
                                 };
@@ -398,34 +332,26 @@ where
             });
         }
         if T::NUMBER_OF_DIGITS % 2 == 1 {
-            self.copy_from_slice(&temp);
+            self.copy_from_slice(temp_slice);
         }
     }
 
     //Rayon
     fn radix_sort4(&mut self) {
-        const PAGE_SIZE: usize = 4096;
         const CHUNK_MULTIPLIER: usize = 2;
         let elements_per_chunk = self
             .len()
             .div_ceil(current_num_threads() * CHUNK_MULTIPLIER);
-        let mut temp = Vec::with_capacity(self.len());
-        unsafe {
-            temp.set_len(self.len());
-            let temp_as_bytes = slice::from_raw_parts_mut(
-                temp.as_mut_ptr() as *mut u8,
-                self.len() * size_of::<T>(),
-            );
-            temp_as_bytes
-                .iter_mut()
-                .step_by(PAGE_SIZE)
-                .for_each(|element| *element = 0);
-        }
+        let mut temp = TempBuffer::<T>::new(self.len());
+        temp.as_mut_slice()
+            .par_chunks_mut(elements_per_chunk)
+            .for_each(prefault_pages);
+        let temp_slice = temp.as_slice();
         for current_digit_index in 0..T::NUMBER_OF_DIGITS {
             let (src, dst) = if current_digit_index % 2 == 0 {
-                (self.as_ref(), temp.as_slice())
+                (&*self, temp_slice)
             } else {
-                (temp.as_slice(), self.as_ref())
+                (temp_slice, &*self)
             };
             let mut bin_histogram_per_chunk = src
                 .par_chunks(elements_per_chunk)
@@ -452,7 +378,7 @@ where
                 .zip(bin_starts_per_chunk)
                 .for_each(|(src_chunk, mut bin_starts)| {
                     for element in src_chunk {
-                        let digit_value = element.get_digit(current_digit_index as u8) as usize;
+                        let digit_value = element.get_digit(current_digit_index) as usize;
                         let dst =
                             unsafe { slice::from_raw_parts_mut(dst.as_ptr() as *mut T, dst.len()) };
                         dst[bin_starts[digit_value]] = *element;
@@ -461,36 +387,30 @@ where
                 });
         }
         if T::NUMBER_OF_DIGITS % 2 == 1 {
-            self.copy_from_slice(&temp);
+            self.copy_from_slice(temp_slice);
         }
     }
 
     //Buffering of writes
     fn radix_sort5(&mut self) {
         const BUFFER_SIZE: usize = 96;
-        const PAGE_SIZE: usize = 4096;
         let elements_per_chunk = self.len().div_ceil(
             available_parallelism()
                 .expect("failed to acquire number of CPUs")
                 .get(),
         );
-        let mut temp = Vec::with_capacity(self.len());
-        unsafe {
-            temp.set_len(self.len());
-            let temp_as_bytes = slice::from_raw_parts_mut(
-                temp.as_mut_ptr() as *mut u8,
-                self.len() * size_of::<T>(),
-            );
-            temp_as_bytes
-                .iter_mut()
-                .step_by(PAGE_SIZE)
-                .for_each(|element| *element = 0);
-        }
+        let mut temp = TempBuffer::<T>::new(self.len());
+        thread::scope(|scope| {
+            for temp_chunk in temp.as_mut_slice().chunks_mut(elements_per_chunk) {
+                scope.spawn(|| prefault_pages(temp_chunk));
+            }
+        });
+        let temp_slice = temp.as_slice();
         for current_digit_index in 0..T::NUMBER_OF_DIGITS {
             let (src, dst) = if current_digit_index % 2 == 0 {
-                (self.as_ref(), temp.as_slice())
+                (&*self, temp_slice)
             } else {
-                (temp.as_slice(), self.as_ref())
+                (temp_slice, &*self)
             };
             let mut bin_histogram_per_chunk = thread::scope(|scope| {
                 let workers = src
@@ -540,7 +460,7 @@ where
                                 if derand_buffer_sizes[digit_value] == BUFFER_SIZE {
                                     unsafe {
                                         copy_nonoverlapping(
-                                            derand_buffers_slice[digit_value].as_ptr() as *const T,
+                                            derand_buffers_slice[digit_value].as_ptr(),
                                             &dst[bin_starts[digit_value]] as *const T as *mut T,
                                             BUFFER_SIZE,
                                         );
@@ -553,7 +473,7 @@ where
                                 if derand_buffer_sizes[digit] > 0 {
                                     unsafe {
                                         copy_nonoverlapping(
-                                            derand_buffers_slice[digit].as_ptr() as *const T,
+                                            derand_buffers_slice[digit].as_ptr(),
                                             &dst[bin_starts[digit]] as *const T as *mut T,
                                             derand_buffer_sizes[digit],
                                         );
@@ -565,7 +485,251 @@ where
             });
         }
         if T::NUMBER_OF_DIGITS % 2 == 1 {
-            self.copy_from_slice(&temp);
+            self.copy_from_slice(temp_slice);
+        }
+    }
+
+    //Histogram of the next digit counted while scattering
+    fn radix_sort6(&mut self) {
+        const BUFFER_SIZE: usize = 96;
+        if self.len() < 2 {
+            return;
+        }
+        let elements_per_chunk = self.len().div_ceil(current_num_threads());
+        let number_of_chunks = self.len().div_ceil(elements_per_chunk);
+        let mut temp = TempBuffer::<T>::new(self.len());
+        temp.as_mut_slice()
+            .par_chunks_mut(elements_per_chunk)
+            .for_each(prefault_pages);
+        let temp_slice = temp.as_slice();
+        let mut bin_histogram_per_chunk = self
+            .par_chunks(elements_per_chunk)
+            .map(|src_chunk| {
+                let mut bin_histogram = [0; 256];
+                for element in src_chunk {
+                    bin_histogram[element.get_digit(0) as usize] += 1;
+                }
+                bin_histogram
+            })
+            .collect::<Vec<_>>();
+        for current_digit_index in 0..T::NUMBER_OF_DIGITS {
+            let (src, dst) = if current_digit_index % 2 == 0 {
+                (&*self, temp_slice)
+            } else {
+                (temp_slice, &*self)
+            };
+            let next_digit_index =
+                (current_digit_index + 1 < T::NUMBER_OF_DIGITS).then_some(current_digit_index + 1);
+            let bin_starts_per_chunk = BinStartsPerChunk::new(&bin_histogram_per_chunk);
+            //Counted by every worker into the histogram of the chunk its elements land in
+            let next_bin_histogram_per_chunk = (0..number_of_chunks)
+                .map(|_| array::from_fn::<_, 256, _>(|_| AtomicUsize::new(0)))
+                .collect::<Vec<_>>();
+            src.par_chunks(elements_per_chunk)
+                .enumerate()
+                .for_each(|(chunk_index, src_chunk)| {
+                    let mut bin_starts = bin_starts_per_chunk.for_chunk(chunk_index);
+                    //Counts of a flushed buffer, added to the shared histogram once per digit
+                    let mut bin_counts = [0; 256];
+                    let mut counted_digits = Vec::with_capacity(BUFFER_SIZE);
+                    let mut flush = |buffer: &[T], bin_start: usize| {
+                        unsafe {
+                            copy_nonoverlapping(
+                                buffer.as_ptr(),
+                                &dst[bin_start] as *const T as *mut T,
+                                buffer.len(),
+                            );
+                        }
+                        let Some(next_digit_index) = next_digit_index else {
+                            return;
+                        };
+                        let mut position = bin_start;
+                        let mut rest = buffer;
+                        while !rest.is_empty() {
+                            let dst_chunk_index = position / elements_per_chunk;
+                            let run_len = ((dst_chunk_index + 1) * elements_per_chunk - position)
+                                .min(rest.len());
+                            let (run, next_rest) = rest.split_at(run_len);
+                            for element in run {
+                                let digit = element.get_digit(next_digit_index) as usize;
+                                if bin_counts[digit] == 0 {
+                                    counted_digits.push(digit);
+                                }
+                                bin_counts[digit] += 1;
+                            }
+                            for digit in counted_digits.drain(..) {
+                                next_bin_histogram_per_chunk[dst_chunk_index][digit]
+                                    .fetch_add(bin_counts[digit], Relaxed);
+                                bin_counts[digit] = 0;
+                            }
+                            position += run_len;
+                            rest = next_rest;
+                        }
+                    };
+                    let mut derand_buffers = MaybeUninit::<[[T; BUFFER_SIZE]; 256]>::uninit();
+                    let derand_buffers_slice = unsafe { derand_buffers.assume_init_mut() };
+                    let mut derand_buffer_sizes = [0; 256];
+                    for element in src_chunk {
+                        let digit_value = element.get_digit(current_digit_index) as usize;
+                        derand_buffers_slice[digit_value][derand_buffer_sizes[digit_value]] =
+                            *element;
+                        derand_buffer_sizes[digit_value] += 1;
+                        if derand_buffer_sizes[digit_value] == BUFFER_SIZE {
+                            flush(&derand_buffers_slice[digit_value], bin_starts[digit_value]);
+                            bin_starts[digit_value] += BUFFER_SIZE;
+                            derand_buffer_sizes[digit_value] = 0;
+                        }
+                    }
+                    for digit in 0..256 {
+                        if derand_buffer_sizes[digit] > 0 {
+                            flush(
+                                &derand_buffers_slice[digit][..derand_buffer_sizes[digit]],
+                                bin_starts[digit],
+                            );
+                        }
+                    }
+                });
+            if next_digit_index.is_some() {
+                bin_histogram_per_chunk = next_bin_histogram_per_chunk
+                    .into_iter()
+                    .map(|bin_histogram| bin_histogram.map(AtomicUsize::into_inner))
+                    .collect();
+            }
+        }
+        if T::NUMBER_OF_DIGITS % 2 == 1 {
+            self.copy_from_slice(temp_slice);
+        }
+    }
+
+    //Partitioning on the top digit, then buckets sorted on their own
+    fn radix_sort7(&mut self) {
+        const BUFFER_SIZE: usize = 96;
+        if self.len() < 2 {
+            return;
+        }
+        let top_digit_index = T::NUMBER_OF_DIGITS - 1;
+        let elements_per_chunk = self.len().div_ceil(current_num_threads());
+        let mut temp = TempBuffer::<T>::new(self.len());
+        temp.as_mut_slice()
+            .par_chunks_mut(elements_per_chunk)
+            .for_each(prefault_pages);
+        let temp_slice = temp.as_slice();
+        let bin_histogram_per_chunk = self
+            .par_chunks(elements_per_chunk)
+            .map(|src_chunk| {
+                let mut bin_histogram = [0; 256];
+                for element in src_chunk {
+                    bin_histogram[element.get_digit(top_digit_index) as usize] += 1;
+                }
+                bin_histogram
+            })
+            .collect::<Vec<_>>();
+        let bin_starts_per_chunk = BinStartsPerChunk::new(&bin_histogram_per_chunk);
+        self.par_chunks(elements_per_chunk)
+            .enumerate()
+            .for_each(|(chunk_index, src_chunk)| {
+                let mut bin_starts = bin_starts_per_chunk.for_chunk(chunk_index);
+                let mut derand_buffers = MaybeUninit::<[[T; BUFFER_SIZE]; 256]>::uninit();
+                let derand_buffers_slice = unsafe { derand_buffers.assume_init_mut() };
+                let mut derand_buffer_sizes = [0; 256];
+                for element in src_chunk {
+                    let digit_value = element.get_digit(top_digit_index) as usize;
+                    derand_buffers_slice[digit_value][derand_buffer_sizes[digit_value]] = *element;
+                    derand_buffer_sizes[digit_value] += 1;
+                    if derand_buffer_sizes[digit_value] == BUFFER_SIZE {
+                        unsafe {
+                            copy_nonoverlapping(
+                                derand_buffers_slice[digit_value].as_ptr(),
+                                &temp_slice[bin_starts[digit_value]] as *const T as *mut T,
+                                BUFFER_SIZE,
+                            );
+                        }
+                        bin_starts[digit_value] += BUFFER_SIZE;
+                        derand_buffer_sizes[digit_value] = 0;
+                    }
+                }
+                for digit in 0..256 {
+                    if derand_buffer_sizes[digit] > 0 {
+                        unsafe {
+                            copy_nonoverlapping(
+                                derand_buffers_slice[digit].as_ptr(),
+                                &temp_slice[bin_starts[digit]] as *const T as *mut T,
+                                derand_buffer_sizes[digit],
+                            );
+                        }
+                    }
+                }
+            });
+        let mut temp_rest =
+            unsafe { slice::from_raw_parts_mut(temp_slice.as_ptr() as *mut T, temp_slice.len()) };
+        let mut data_rest = &mut *self;
+        let mut buckets = (0..256)
+            .map(|digit| {
+                let bucket_len = bin_histogram_per_chunk
+                    .iter()
+                    .map(|bin_histogram| bin_histogram[digit])
+                    .sum();
+                let (temp_bucket, temp_tail) = mem::take(&mut temp_rest).split_at_mut(bucket_len);
+                let (data_bucket, data_tail) = mem::take(&mut data_rest).split_at_mut(bucket_len);
+                temp_rest = temp_tail;
+                data_rest = data_tail;
+                (temp_bucket, data_bucket)
+            })
+            .filter(|(temp_bucket, _)| !temp_bucket.is_empty())
+            .collect::<Vec<_>>();
+        //Largest buckets first, so that a skewed input does not end on one long bucket
+        buckets.sort_unstable_by_key(|(temp_bucket, _)| usize::MAX - temp_bucket.len());
+        buckets
+            .into_par_iter()
+            .for_each(|(temp_bucket, data_bucket)| {
+                //Buckets too large for a single thread are sorted by all of them
+                if temp_bucket.len() > elements_per_chunk {
+                    data_bucket.copy_from_slice(temp_bucket);
+                    let options = RadixSortOptions::from_env_cached();
+                    lsd_sort(
+                        data_bucket,
+                        options,
+                        &Chunks::new(data_bucket.len(), options, ChunkRunner::Pool),
+                        &KeyDigits,
+                        0..top_digit_index,
+                    );
+                } else if !lsd_passes(temp_bucket, data_bucket, 0..top_digit_index) {
+                    data_bucket.copy_from_slice(temp_bucket);
+                }
+            });
+    }
+}
+
+//Passes alternating between two slices, true if the result ends in `temp`
+fn lsd_passes<T>(data: &mut [T], temp: &mut [T], digit_indices: Range<u8>) -> bool
+where
+    T: RadixDigits + Copy,
+{
+    let mut sorted_in_temp = false;
+    for current_digit_index in digit_indices {
+        let (src, dst) = if sorted_in_temp {
+            (&*temp, &mut *data)
+        } else {
+            (&*data, &mut *temp)
+        };
+        let mut bin_histogram = [0; 256];
+        for element in src {
+            bin_histogram[element.get_digit(current_digit_index) as usize] += 1;
+        }
+        let mut bin_starts = {
+            bin_histogram.iter_mut().fold(0, |bin_start, bin_count| {
+                let next_bin_start = bin_start + *bin_count;
+                *bin_count = bin_start;
+                next_bin_start
+            });
+            bin_histogram
+        };
+        for element in src {
+            let digit_value = element.get_digit(current_digit_index) as usize;
+            dst[bin_starts[digit_value]] = *element;
+            bin_starts[digit_value] += 1;
         }
+        sorted_in_temp = !sorted_in_temp;
     }
+    sorted_in_temp
 }
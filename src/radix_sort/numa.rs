@@ -0,0 +1,100 @@
+use rayon::ThreadPool;
+use std::sync::OnceLock;
+
+//Pool with a worker pinned to every allowed CPU, ordered by NUMA node,
+//or None on single node machines and when the topology is unknown
+pub(crate) fn pinned_pool() -> Option<&'static ThreadPool> {
+    static POOL: OnceLock<Option<ThreadPool>> = OnceLock::new();
+    POOL.get_or_init(build_pinned_pool).as_ref()
+}
+
+#[cfg(target_os = "linux")]
+fn build_pinned_pool() -> Option<ThreadPool> {
+    let allowed_cpus = allowed_cpus()?;
+    let cpus_per_node = read_nodes()?
+        .into_iter()
+        .map(|cpus| {
+            cpus.into_iter()
+                .filter(|cpu| allowed_cpus.contains(cpu))
+                .collect::<Vec<_>>()
+        })
+        .filter(|cpus| !cpus.is_empty())
+        .collect::<Vec<_>>();
+    if cpus_per_node.len() < 2 {
+        return None;
+    }
+    let cpus = cpus_per_node.concat();
+    rayon::ThreadPoolBuilder::new()
+        .num_threads(cpus.len())
+        .thread_name(|index| format!("radix-sort-numa-{index}"))
+        .start_handler(move |index| pin_to_cpu(cpus[index]))
+        .build()
+        .ok()
+}
+
+#[cfg(not(target_os = "linux"))]
+fn build_pinned_pool() -> Option<ThreadPool> {
+    None
+}
+
+//CPUs of every node, in the order of node numbers
+#[cfg(target_os = "linux")]
+fn read_nodes() -> Option<Vec<Vec<usize>>> {
+    let mut nodes = std::fs::read_dir("/sys/devices/system/node")
+        .ok()?
+        .filter_map(|entry| {
+            let entry = entry.ok()?;
+            let node = entry
+                .file_name()
+                .to_str()?
+                .strip_prefix("node")?
+                .parse::<usize>()
+                .ok()?;
+            let cpu_list = std::fs::read_to_string(entry.path().join("cpulist")).ok()?;
+            Some((node, parse_cpu_list(&cpu_list)?))
+        })
+        .collect::<Vec<_>>();
+    nodes.sort();
+    Some(nodes.into_iter().map(|(_, cpus)| cpus).collect())
+}
+
+//Parses lists like "0-3,8-11"
+pub(crate) fn parse_cpu_list(cpu_list: &str) -> Option<Vec<usize>> {
+    let cpu_list = cpu_list.trim();
+    if cpu_list.is_empty() {
+        return Some(vec![]);
+    }
+    let mut cpus = vec![];
+    for range in cpu_list.split(',') {
+        match range.split_once('-') {
+            Some((first, last)) => cpus.extend(first.parse::<usize>().ok()?..=last.parse().ok()?),
+            None => cpus.push(range.parse().ok()?),
+        }
+    }
+    Some(cpus)
+}
+
+#[cfg(target_os = "linux")]
+fn allowed_cpus() -> Option<Vec<usize>> {
+    unsafe {
+        let mut set = std::mem::zeroed::<libc::cpu_set_t>();
+        if libc::sched_getaffinity(0, size_of::<libc::cpu_set_t>(), &mut set) != 0 {
+            return None;
+        }
+        Some(
+            (0..libc::CPU_SETSIZE as usize)
+                .filter(|cpu| libc::CPU_ISSET(*cpu, &set))
+                .collect(),
+        )
+    }
+}
+
+//Failing to pin only costs locality
+#[cfg(target_os = "linux")]
+fn pin_to_cpu(cpu: usize) {
+    unsafe {
+        let mut set = std::mem::zeroed::<libc::cpu_set_t>();
+        libc::CPU_SET(cpu, &mut set);
+        libc::sched_setaffinity(0, size_of::<libc::cpu_set_t>(), &set);
+    }
+}
@@ -0,0 +1,161 @@
+use super::stream::CACHE_LINE;
+use rayon::current_num_threads;
+use std::{env, str::FromStr, sync::OnceLock};
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RadixSortOptions {
+    //Number of workers the input is split among, 0 means the size of the current pool
+    pub threads: usize,
+    //Number of chunks per worker
+    pub chunk_multiplier: usize,
+    //Size of the write buffer of every bin in bytes
+    pub buffer_bytes: usize,
+    //Touch every page of temp memory before the first pass
+    pub prefault: bool,
+    //Inputs shorter than this are sorted on the calling thread
+    pub small_input_threshold: usize,
+    //Flush write buffers with non-temporal stores where the CPU supports them
+    pub streaming_stores: bool,
+    //Keep chunks on workers pinned to the CPUs of every NUMA node, Linux only
+    pub numa: bool,
+    //Find the smallest and largest key first and sort only the digits of their difference
+    pub key_range: bool,
+    //Write elements of a bin holding nearly all of a chunk in runs instead of through the buffers
+    pub dominant_runs: bool,
+    //Elements of at least this many bytes are sorted as (key, index) pairs and moved once
+    pub indirect_threshold: usize,
+}
+
+impl Default for RadixSortOptions {
+    fn default() -> Self {
+        Self {
+            threads: 0,
+            chunk_multiplier: 1,
+            buffer_bytes: 1024,
+            prefault: true,
+            small_input_threshold: 1 << 14,
+            streaming_stores: false,
+            numa: false,
+            key_range: false,
+            dominant_runs: true,
+            //Crossover measured with `cargo run -r -- indirect`
+            indirect_threshold: 64,
+        }
+    }
+}
+
+impl RadixSortOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    //Defaults overridden by RADIX_SORT_* environment variables
+    pub fn from_env() -> Self {
+        let mut options = Self::default();
+        override_from_env(&mut options.threads, "RADIX_SORT_THREADS");
+        override_from_env(&mut options.chunk_multiplier, "RADIX_SORT_CHUNK_MULTIPLIER");
+        override_from_env(&mut options.buffer_bytes, "RADIX_SORT_BUFFER_BYTES");
+        override_from_env(&mut options.prefault, "RADIX_SORT_PREFAULT");
+        override_from_env(
+            &mut options.small_input_threshold,
+            "RADIX_SORT_SMALL_INPUT_THRESHOLD",
+        );
+        override_from_env(&mut options.streaming_stores, "RADIX_SORT_STREAMING_STORES");
+        override_from_env(&mut options.numa, "RADIX_SORT_NUMA");
+        override_from_env(&mut options.key_range, "RADIX_SORT_KEY_RANGE");
+        override_from_env(&mut options.dominant_runs, "RADIX_SORT_DOMINANT_RUNS");
+        override_from_env(
+            &mut options.indirect_threshold,
+            "RADIX_SORT_INDIRECT_THRESHOLD",
+        );
+        options
+    }
+
+    pub(crate) fn from_env_cached() -> &'static Self {
+        static OPTIONS: OnceLock<RadixSortOptions> = OnceLock::new();
+        OPTIONS.get_or_init(Self::from_env)
+    }
+
+    pub fn threads(mut self, threads: usize) -> Self {
+        self.threads = threads;
+        self
+    }
+
+    pub fn chunk_multiplier(mut self, chunk_multiplier: usize) -> Self {
+        self.chunk_multiplier = chunk_multiplier;
+        self
+    }
+
+    pub fn buffer_bytes(mut self, buffer_bytes: usize) -> Self {
+        self.buffer_bytes = buffer_bytes;
+        self
+    }
+
+    pub fn prefault(mut self, prefault: bool) -> Self {
+        self.prefault = prefault;
+        self
+    }
+
+    pub fn small_input_threshold(mut self, small_input_threshold: usize) -> Self {
+        self.small_input_threshold = small_input_threshold;
+        self
+    }
+
+    pub fn streaming_stores(mut self, streaming_stores: bool) -> Self {
+        self.streaming_stores = streaming_stores;
+        self
+    }
+
+    pub fn numa(mut self, numa: bool) -> Self {
+        self.numa = numa;
+        self
+    }
+
+    pub fn key_range(mut self, key_range: bool) -> Self {
+        self.key_range = key_range;
+        self
+    }
+
+    pub fn dominant_runs(mut self, dominant_runs: bool) -> Self {
+        self.dominant_runs = dominant_runs;
+        self
+    }
+
+    pub fn indirect_threshold(mut self, indirect_threshold: usize) -> Self {
+        self.indirect_threshold = indirect_threshold;
+        self
+    }
+
+    pub(crate) fn number_of_chunks(&self, len: usize) -> usize {
+        if len < self.small_input_threshold {
+            return 1;
+        }
+        let threads = if self.threads == 0 {
+            current_num_threads()
+        } else {
+            self.threads
+        };
+        (threads * self.chunk_multiplier.max(1)).clamp(1, len.max(1))
+    }
+
+    //Whole cache lines per bin for elements that evenly divide one
+    pub(crate) fn buffer_len<T>(&self) -> usize {
+        let element_size = size_of::<T>().max(1);
+        let buffer_len = self.buffer_bytes / element_size;
+        if CACHE_LINE.is_multiple_of(element_size) {
+            let elements_per_line = CACHE_LINE / element_size;
+            (buffer_len - buffer_len % elements_per_line).max(elements_per_line)
+        } else {
+            buffer_len.max(1)
+        }
+    }
+}
+
+fn override_from_env<T: FromStr>(value: &mut T, name: &str) {
+    if let Ok(text) = env::var(name) {
+        match text.trim().parse() {
+            Ok(parsed) => *value = parsed,
+            Err(_) => eprintln!("ignoring invalid value of {name}: {text:?}"),
+        }
+    }
+}
@@ -0,0 +1,276 @@
+use super::{
+    runner::{ChunkRunner, Chunks},
+    scratch::TempBuffer,
+    RadixSortOptions,
+};
+use rayon::{
+    iter::{IndexedParallelIterator, IntoParallelRefIterator, ParallelIterator},
+    slice::ParallelSliceMut,
+};
+use std::{
+    mem::MaybeUninit,
+    ptr::copy_nonoverlapping,
+    sync::atomic::{AtomicU64, AtomicUsize, Ordering::Relaxed},
+};
+
+//Indices checked once to be a permutation, so that they are applied to any number of columns
+//without checking them again
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Permutation {
+    indices: Vec<usize>,
+}
+
+impl Permutation {
+    //Panics unless every index below the length appears exactly once
+    pub fn new(indices: Vec<usize>) -> Self {
+        check_permutation(&indices);
+        Self { indices }
+    }
+
+    pub fn indices(&self) -> &[usize] {
+        &self.indices
+    }
+
+    pub fn into_indices(self) -> Vec<usize> {
+        self.indices
+    }
+
+    //Same as `apply_permutation`, moving the elements through `scratch`, which is grown to the
+    //length of `data` and can be passed again for the next column
+    pub fn apply<T: Send + Sync>(&self, data: &mut [T], scratch: &mut Vec<MaybeUninit<T>>) {
+        let indices = &self.indices;
+        assert_eq!(
+            data.len(),
+            indices.len(),
+            "permutation of a different length"
+        );
+        let chunks = Chunks::new(
+            data.len(),
+            RadixSortOptions::from_env_cached(),
+            ChunkRunner::Pool,
+        );
+        permute_by(data, grow(scratch, indices.len()), &chunks, |index| {
+            (indices[index], index)
+        });
+    }
+
+    //Same as `apply_inverse_permutation`, moving the elements through `scratch` as `apply` does
+    pub fn apply_inverse<T: Send + Sync>(&self, data: &mut [T], scratch: &mut Vec<MaybeUninit<T>>) {
+        let indices = &self.indices;
+        assert_eq!(
+            data.len(),
+            indices.len(),
+            "permutation of a different length"
+        );
+        let chunks = Chunks::new(
+            data.len(),
+            RadixSortOptions::from_env_cached(),
+            ChunkRunner::Pool,
+        );
+        permute_by(data, grow(scratch, indices.len()), &chunks, |index| {
+            (index, indices[index])
+        });
+    }
+}
+
+//Reorders `data` so that its element at `index` is the one previously at `permutation[index]`
+pub fn apply_permutation<T: Send + Sync>(data: &mut [T], permutation: &[usize]) {
+    apply_checked(data, permutation, |index| (permutation[index], index));
+}
+
+//Reorders `data` so that its element at `permutation[index]` is the one previously at `index`
+pub fn apply_inverse_permutation<T: Send + Sync>(data: &mut [T], permutation: &[usize]) {
+    apply_checked(data, permutation, |index| (index, permutation[index]));
+}
+
+fn apply_checked<T, F>(data: &mut [T], permutation: &[usize], moves: F)
+where
+    T: Send + Sync,
+    F: Fn(usize) -> (usize, usize) + Sync,
+{
+    assert_eq!(
+        data.len(),
+        permutation.len(),
+        "permutation of a different length"
+    );
+    //A repeated index would duplicate elements
+    check_permutation(permutation);
+    let chunks = Chunks::new(
+        data.len(),
+        RadixSortOptions::from_env_cached(),
+        ChunkRunner::Pool,
+    );
+    let mut temp = TempBuffer::<T>::new(data.len());
+    permute_by(data, temp.as_mut_slice(), &chunks, moves);
+}
+
+//Panics unless every index below the length appears exactly once
+pub fn invert_permutation(permutation: &[usize]) -> Vec<usize> {
+    let inverse = (0..permutation.len())
+        .map(|_| AtomicUsize::new(usize::MAX))
+        .collect::<Vec<_>>();
+    permutation
+        .par_iter()
+        .enumerate()
+        .for_each(|(index, &target)| {
+            let claimed = inverse.get(target).is_some_and(|slot| {
+                slot.compare_exchange(usize::MAX, index, Relaxed, Relaxed)
+                    .is_ok()
+            });
+            assert!(
+                claimed,
+                "not a permutation, {target} out of range or repeated"
+            );
+        });
+    inverse.into_iter().map(AtomicUsize::into_inner).collect()
+}
+
+//Marks every index in a bit set, a bit per index rather than the word per index of an inverse
+fn check_permutation(permutation: &[usize]) {
+    let seen = (0..permutation.len().div_ceil(64))
+        .map(|_| AtomicU64::new(0))
+        .collect::<Vec<_>>();
+    permutation.par_iter().for_each(|&index| {
+        let bit = 1 << (index % 64);
+        let claimed =
+            index < permutation.len() && seen[index / 64].fetch_or(bit, Relaxed) & bit == 0;
+        assert!(
+            claimed,
+            "not a permutation, {index} out of range or repeated"
+        );
+    });
+}
+
+fn grow<T>(scratch: &mut Vec<MaybeUninit<T>>, len: usize) -> &mut [MaybeUninit<T>] {
+    scratch.reserve(len.saturating_sub(scratch.len()));
+    //Uninitialized memory is a valid MaybeUninit
+    unsafe {
+        scratch.set_len(scratch.capacity());
+    }
+    &mut scratch[..len]
+}
+
+//Moves the element at `source` to `target` for every (source, target) given for the indices,
+//into temp memory as large as `data` in parallel and back
+fn permute_by<T, F>(data: &mut [T], temp: &mut [MaybeUninit<T>], chunks: &Chunks, moves: F)
+where
+    T: Send + Sync,
+    F: Fn(usize) -> (usize, usize) + Sync,
+{
+    let temp_slice = &*temp;
+    let elements = &*data;
+    chunks.run(|chunk_index| {
+        for index in chunks.range(chunk_index) {
+            let (source, target) = moves(index);
+            unsafe {
+                copy_nonoverlapping(&elements[source], temp_slice[target].as_ptr() as *mut T, 1);
+            }
+        }
+    });
+    chunks.run(|chunk_index| {
+        let range = chunks.range(chunk_index);
+        unsafe {
+            copy_nonoverlapping(
+                temp_slice[range.clone()].as_ptr() as *const T,
+                &elements[range.start] as *const T as *mut T,
+                range.len(),
+            );
+        }
+    });
+}
+
+//Moves the element at `source(index)` to `index` for every index in place, following the cycles
+//of the permutation from every chunk in parallel. Cycles longer than a chunk, or met by walks from
+//several chunks, are split into segments, and only the first element of every segment is set
+//aside, so that the segments are moved in parallel too.
+pub(crate) fn permute_in_place<T, F>(data: &mut [T], chunks: &Chunks, source: F)
+where
+    T: Send + Sync,
+    F: Fn(usize) -> usize + Sync,
+{
+    let visited = (0..data.len().div_ceil(64))
+        .map(|_| AtomicU64::new(0))
+        .collect::<Vec<_>>();
+    //True for the walk that visits the index first
+    let visit = |index: usize| {
+        let bit = 1 << (index % 64);
+        visited[index / 64].fetch_or(bit, Relaxed) & bit == 0
+    };
+    let elements = &*data;
+    //Moves the elements along the walk from `start` to `end`, each from the next index of the walk
+    let move_walk = |start: usize, end: usize| {
+        let mut index = start;
+        while index != end {
+            let next = source(index);
+            unsafe {
+                copy_nonoverlapping(&elements[next], &elements[index] as *const T as *mut T, 1);
+            }
+            index = next;
+        }
+    };
+    let mut segments = chunks
+        .run(|chunk_index| {
+            let first = TempBuffer::<T>::new(1);
+            let mut segments = Vec::new();
+            for cycle_start in chunks.range(chunk_index) {
+                if source(cycle_start) == cycle_start || !visit(cycle_start) {
+                    continue;
+                }
+                let (mut start, mut end) = (cycle_start, cycle_start);
+                let mut segment_len = 1;
+                while visit(source(end)) {
+                    //Long cycles walked by one chunk are moved in parallel all the same
+                    if segment_len == chunks.elements_per_chunk {
+                        segments.push((start, end));
+                        start = source(end);
+                        segment_len = 0;
+                    }
+                    end = source(end);
+                    segment_len += 1;
+                }
+                //Another walk got to the next index first, which is where it started
+                if source(end) != start {
+                    segments.push((start, end));
+                    continue;
+                }
+                //The whole cycle was walked from here, so it is moved right away
+                unsafe {
+                    copy_nonoverlapping(&elements[start], first.as_slice().as_ptr() as *mut T, 1);
+                    move_walk(start, end);
+                    copy_nonoverlapping(
+                        first.as_slice().as_ptr(),
+                        &elements[end] as *const T as *mut T,
+                        1,
+                    );
+                }
+            }
+            segments
+        })
+        .concat();
+    if segments.is_empty() {
+        return;
+    }
+    segments.par_sort_unstable_by_key(|&(start, _)| start);
+    let firsts = TempBuffer::<T>::new(segments.len());
+    let firsts_slice = firsts.as_slice();
+    segments
+        .par_iter()
+        .zip(firsts_slice)
+        .for_each(|(&(start, _), first)| unsafe {
+            copy_nonoverlapping(&elements[start], first as *const T as *mut T, 1);
+        });
+    //Every segment ends with the first element of the segment following it on the cycle
+    segments.par_iter().for_each(|&(start, end)| {
+        move_walk(start, end);
+        let next_segment = segments
+            .binary_search_by_key(&source(end), |&(start, _)| start)
+            .unwrap_or_else(|_| unreachable!("walk ended before the start of another"));
+        unsafe {
+            copy_nonoverlapping(
+                &firsts_slice[next_segment],
+                &elements[end] as *const T as *mut T,
+                1,
+            );
+        }
+    });
+}
@@ -0,0 +1,23 @@
+/// Types whose values are nothing but their bytes, so that they are read from and written to
+/// files as they are.
+///
+/// # Safety
+///
+/// Implementors have no padding, so every byte of a value is initialized, and every pattern of
+/// bytes of their size is a valid value. `bool`, `char` and pairs of types of different sizes,
+/// which are padded, are not plain data.
+pub unsafe trait PlainData: Copy + Send + Sync + 'static {}
+
+macro_rules! plain_data {
+    ($($t:ty),*) => {
+        $(
+            unsafe impl PlainData for $t {}
+        )*
+    };
+}
+
+plain_data!(u8, u16, u32, u64, u128, usize, i8, i16, i32, i64, i128, isize, f32, f64);
+
+//Both halves have the same size, which is a multiple of their alignment, so there is no padding
+//between or after them
+unsafe impl<T: PlainData> PlainData for (T, T) {}
@@ -0,0 +1,135 @@
+use std::sync::atomic::{AtomicUsize, Ordering::AcqRel};
+
+//Chunks scanned by one task, so that the scan is split between as many tasks as there are groups
+const CHUNKS_PER_GROUP: usize = 16;
+
+//Bin histograms of the chunks of a pass, every group of chunks scanned by the task counting the
+//last chunk of the group, so that the scan is done within the histogram job
+pub(crate) struct HistogramScan {
+    bin_histogram_per_chunk: Vec<[usize; 256]>,
+    //Offsets of every chunk within its group, per digit
+    offsets_per_chunk: Vec<[usize; 256]>,
+    //Counts of every group, per digit
+    totals_per_group: Vec<[usize; 256]>,
+    chunks_left_per_group: Vec<AtomicUsize>,
+}
+
+impl HistogramScan {
+    pub(crate) fn new(number_of_chunks: usize) -> Self {
+        let number_of_groups = number_of_chunks.div_ceil(CHUNKS_PER_GROUP);
+        Self {
+            bin_histogram_per_chunk: vec![[0; 256]; number_of_chunks],
+            offsets_per_chunk: vec![[0; 256]; number_of_chunks],
+            totals_per_group: vec![[0; 256]; number_of_groups],
+            chunks_left_per_group: (0..number_of_groups)
+                .map(|group| {
+                    AtomicUsize::new(
+                        CHUNKS_PER_GROUP.min(number_of_chunks - group * CHUNKS_PER_GROUP),
+                    )
+                })
+                .collect(),
+        }
+    }
+
+    //Called once for every chunk, from any thread
+    pub(crate) fn add(&self, chunk_index: usize, bin_histogram: [usize; 256]) {
+        unsafe {
+            (&self.bin_histogram_per_chunk[chunk_index] as *const [usize; 256]
+                as *mut [usize; 256])
+                .write(bin_histogram);
+        }
+        let group = chunk_index / CHUNKS_PER_GROUP;
+        //Histograms of the other chunks of the group are visible to the task that counted last
+        if self.chunks_left_per_group[group].fetch_sub(1, AcqRel) != 1 {
+            return;
+        }
+        let chunks = group * CHUNKS_PER_GROUP
+            ..((group + 1) * CHUNKS_PER_GROUP).min(self.bin_histogram_per_chunk.len());
+        let mut prefix_sums = [0; 256];
+        for chunk_index in chunks {
+            unsafe {
+                (&self.offsets_per_chunk[chunk_index] as *const [usize; 256] as *mut [usize; 256])
+                    .write(prefix_sums);
+            }
+            for (prefix_sum, bin_count) in prefix_sums
+                .iter_mut()
+                .zip(&self.bin_histogram_per_chunk[chunk_index])
+            {
+                *prefix_sum += bin_count;
+            }
+        }
+        unsafe {
+            (&self.totals_per_group[group] as *const [usize; 256] as *mut [usize; 256])
+                .write(prefix_sums);
+        }
+    }
+
+    //Once every chunk is added, only the totals of the groups are left to be summed up
+    pub(crate) fn finish(self) -> (Vec<[usize; 256]>, BinStartsPerChunk) {
+        let mut group_starts = Vec::with_capacity(self.totals_per_group.len());
+        let mut bin_counts = [0; 256];
+        for group_counts in &self.totals_per_group {
+            group_starts.push(bin_counts);
+            for (bin_count, group_count) in bin_counts.iter_mut().zip(group_counts) {
+                *bin_count += group_count;
+            }
+        }
+        let mut digit_starts = [0; 256];
+        let mut bin_start = 0;
+        for (digit_start, bin_count) in digit_starts.iter_mut().zip(&bin_counts) {
+            *digit_start = bin_start;
+            bin_start += bin_count;
+        }
+        let bin_starts_per_chunk = BinStartsPerChunk {
+            len: bin_start,
+            digit_starts,
+            group_starts,
+            offsets_per_chunk: self.offsets_per_chunk,
+        };
+        (self.bin_histogram_per_chunk, bin_starts_per_chunk)
+    }
+}
+
+//Exclusive prefix sum over the (digit, chunk) matrix of bin histograms
+pub(crate) struct BinStartsPerChunk {
+    len: usize,
+    digit_starts: [usize; 256],
+    //Offsets of every group of chunks within a bin
+    group_starts: Vec<[usize; 256]>,
+    offsets_per_chunk: Vec<[usize; 256]>,
+}
+
+impl BinStartsPerChunk {
+    //For histograms counted apart from the scan, such as one per thread
+    pub(crate) fn new(bin_histogram_per_chunk: &[[usize; 256]]) -> Self {
+        let scan = HistogramScan::new(bin_histogram_per_chunk.len());
+        for (chunk_index, bin_histogram) in bin_histogram_per_chunk.iter().enumerate() {
+            scan.add(chunk_index, *bin_histogram);
+        }
+        scan.finish().1
+    }
+
+    //All elements share the digit, so the pass would not move anything
+    pub(crate) fn single_bin(&self) -> bool {
+        (0..256).any(|digit| {
+            let bin_end = self
+                .digit_starts
+                .get(digit + 1)
+                .copied()
+                .unwrap_or(self.len);
+            bin_end - self.digit_starts[digit] == self.len
+        })
+    }
+
+    pub(crate) fn for_chunk(&self, chunk_index: usize) -> [usize; 256] {
+        let mut bin_starts = self.digit_starts;
+        for ((bin_start, group_start), offset) in bin_starts
+            .iter_mut()
+            .zip(&self.group_starts[chunk_index / CHUNKS_PER_GROUP])
+            .zip(&self.offsets_per_chunk[chunk_index])
+        {
+            *bin_start += group_start + offset;
+        }
+        bin_starts
+    }
+}
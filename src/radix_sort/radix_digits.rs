@@ -1,22 +1,82 @@
+use super::histogram::{self, KeyTransform};
+use std::mem::offset_of;
+
 pub trait RadixDigits: Send + Sync {
     const NUMBER_OF_DIGITS: u8;
+    //Number of distinct values of `radix_key`, set for keys that are counted instead of sorted by digits
+    const KEY_DOMAIN: Option<usize> = None;
 
     fn get_digit(&self, index: u8) -> u8;
+
+    fn histogram(elements: &[Self], index: u8) -> [usize; 256]
+    where
+        Self: Sized,
+    {
+        histogram::multi_histogram(elements, |element| element.get_digit(index))
+    }
+
+    /// Histogram of the digit of the keys of this type held at `offset` bytes within every
+    /// element, for elements keyed by a field such as the first half of a pair.
+    ///
+    /// # Safety
+    ///
+    /// Every element holds a value of this type at `offset` bytes from its start.
+    unsafe fn field_histogram<E>(elements: &[E], offset: usize, index: u8) -> [usize; 256]
+    where
+        Self: Sized,
+    {
+        histogram::multi_histogram(elements, |element| {
+            (*((element as *const E).byte_add(offset) as *const Self)).get_digit(index)
+        })
+    }
+
+    //All digits as one number, for keys of up to 16 digits
+    fn radix_key(&self) -> u128 {
+        (0..Self::NUMBER_OF_DIGITS).fold(0, |key, index| {
+            key | (self.get_digit(index) as u128) << (index * 8)
+        })
+    }
+
+    //Element made of nothing but the key, None for elements carrying more than their key
+    fn from_radix_key(_key: u128) -> Option<Self>
+    where
+        Self: Sized,
+    {
+        None
+    }
 }
 
 impl RadixDigits for u8 {
     const NUMBER_OF_DIGITS: u8 = 1;
+    const KEY_DOMAIN: Option<usize> = Some(1 << 8);
 
     fn get_digit(&self, index: u8) -> u8 {
-        (*self >> index * 8) as u8
+        *self >> (index * 8)
+    }
+
+    fn radix_key(&self) -> u128 {
+        *self as u128
+    }
+
+    fn from_radix_key(key: u128) -> Option<Self> {
+        Some(key as u8)
     }
 }
 
 impl RadixDigits for u16 {
     const NUMBER_OF_DIGITS: u8 = 2;
+    const KEY_DOMAIN: Option<usize> = Some(1 << 16);
 
     fn get_digit(&self, index: u8) -> u8 {
-        (*self >> index * 8) as u8
+        (*self >> (index * 8)) as u8
+    }
+
+    fn radix_key(&self) -> u128 {
+        *self as u128
+    }
+
+    fn from_radix_key(key: u128) -> Option<Self> {
+        Some(key as u16)
     }
 }
 
@@ -24,7 +84,19 @@ impl RadixDigits for u32 {
     const NUMBER_OF_DIGITS: u8 = 4;
 
     fn get_digit(&self, index: u8) -> u8 {
-        (*self >> index * 8) as u8
+        (*self >> (index * 8)) as u8
+    }
+
+    fn histogram(elements: &[Self], index: u8) -> [usize; 256] {
+        histogram::histogram_32(elements, index, KeyTransform::Unsigned)
+    }
+
+    unsafe fn field_histogram<E>(elements: &[E], offset: usize, index: u8) -> [usize; 256] {
+        histogram::field_histogram_32(elements, offset, index, KeyTransform::Unsigned)
+    }
+
+    fn radix_key(&self) -> u128 {
+        *self as u128
     }
 }
 
@@ -32,7 +104,19 @@ impl RadixDigits for u64 {
     const NUMBER_OF_DIGITS: u8 = 8;
 
     fn get_digit(&self, index: u8) -> u8 {
-        (*self >> index * 8) as u8
+        (*self >> (index * 8)) as u8
+    }
+
+    fn histogram(elements: &[Self], index: u8) -> [usize; 256] {
+        histogram::histogram_64(elements, index, KeyTransform::Unsigned)
+    }
+
+    unsafe fn field_histogram<E>(elements: &[E], offset: usize, index: u8) -> [usize; 256] {
+        histogram::field_histogram_64(elements, offset, index, KeyTransform::Unsigned)
+    }
+
+    fn radix_key(&self) -> u128 {
+        *self as u128
     }
 }
 
@@ -40,7 +124,11 @@ impl RadixDigits for u128 {
     const NUMBER_OF_DIGITS: u8 = 16;
 
     fn get_digit(&self, index: u8) -> u8 {
-        (*self >> index * 8) as u8
+        (*self >> (index * 8)) as u8
+    }
+
+    fn radix_key(&self) -> u128 {
+        *self
     }
 }
 
@@ -48,23 +136,45 @@ impl RadixDigits for usize {
     const NUMBER_OF_DIGITS: u8 = size_of::<usize>() as u8;
 
     fn get_digit(&self, index: u8) -> u8 {
-        (*self >> index * 8) as u8
+        (*self >> (index * 8)) as u8
+    }
+
+    fn radix_key(&self) -> u128 {
+        *self as u128
     }
 }
 
 impl RadixDigits for i8 {
     const NUMBER_OF_DIGITS: u8 = 1;
+    const KEY_DOMAIN: Option<usize> = Some(1 << 8);
 
     fn get_digit(&self, index: u8) -> u8 {
-        ((*self ^ i8::MIN) >> index * 8) as u8
+        ((*self ^ i8::MIN) >> (index * 8)) as u8
+    }
+
+    fn radix_key(&self) -> u128 {
+        (*self ^ i8::MIN) as u8 as u128
+    }
+
+    fn from_radix_key(key: u128) -> Option<Self> {
+        Some((key as u8 as i8) ^ i8::MIN)
     }
 }
 
 impl RadixDigits for i16 {
     const NUMBER_OF_DIGITS: u8 = 2;
+    const KEY_DOMAIN: Option<usize> = Some(1 << 16);
 
     fn get_digit(&self, index: u8) -> u8 {
-        ((*self ^ i16::MIN) >> index * 8) as u8
+        ((*self ^ i16::MIN) >> (index * 8)) as u8
+    }
+
+    fn radix_key(&self) -> u128 {
+        (*self ^ i16::MIN) as u16 as u128
+    }
+
+    fn from_radix_key(key: u128) -> Option<Self> {
+        Some((key as u16 as i16) ^ i16::MIN)
     }
 }
 
@@ -72,7 +182,19 @@ impl RadixDigits for i32 {
     const NUMBER_OF_DIGITS: u8 = 4;
 
     fn get_digit(&self, index: u8) -> u8 {
-        ((*self ^ i32::MIN) >> index * 8) as u8
+        ((*self ^ i32::MIN) >> (index * 8)) as u8
+    }
+
+    fn histogram(elements: &[Self], index: u8) -> [usize; 256] {
+        histogram::histogram_32(elements, index, KeyTransform::Signed)
+    }
+
+    unsafe fn field_histogram<E>(elements: &[E], offset: usize, index: u8) -> [usize; 256] {
+        histogram::field_histogram_32(elements, offset, index, KeyTransform::Signed)
+    }
+
+    fn radix_key(&self) -> u128 {
+        (*self ^ i32::MIN) as u32 as u128
     }
 }
 
@@ -80,7 +202,19 @@ impl RadixDigits for i64 {
     const NUMBER_OF_DIGITS: u8 = 8;
 
     fn get_digit(&self, index: u8) -> u8 {
-        ((*self ^ i64::MIN) >> index * 8) as u8
+        ((*self ^ i64::MIN) >> (index * 8)) as u8
+    }
+
+    fn histogram(elements: &[Self], index: u8) -> [usize; 256] {
+        histogram::histogram_64(elements, index, KeyTransform::Signed)
+    }
+
+    unsafe fn field_histogram<E>(elements: &[E], offset: usize, index: u8) -> [usize; 256] {
+        histogram::field_histogram_64(elements, offset, index, KeyTransform::Signed)
+    }
+
+    fn radix_key(&self) -> u128 {
+        (*self ^ i64::MIN) as u64 as u128
     }
 }
 
@@ -88,7 +222,11 @@ impl RadixDigits for i128 {
     const NUMBER_OF_DIGITS: u8 = 16;
 
     fn get_digit(&self, index: u8) -> u8 {
-        ((*self ^ i128::MIN) >> index * 8) as u8
+        ((*self ^ i128::MIN) >> (index * 8)) as u8
+    }
+
+    fn radix_key(&self) -> u128 {
+        (*self ^ i128::MIN) as u128
     }
 }
 
@@ -96,7 +234,28 @@ impl RadixDigits for isize {
     const NUMBER_OF_DIGITS: u8 = size_of::<isize>() as u8;
 
     fn get_digit(&self, index: u8) -> u8 {
-        ((*self ^ isize::MIN) >> index * 8) as u8
+        ((*self ^ isize::MIN) >> (index * 8)) as u8
+    }
+
+    fn radix_key(&self) -> u128 {
+        (*self ^ isize::MIN) as usize as u128
+    }
+}
+
+impl RadixDigits for bool {
+    const NUMBER_OF_DIGITS: u8 = 1;
+    const KEY_DOMAIN: Option<usize> = Some(2);
+
+    fn get_digit(&self, index: u8) -> u8 {
+        (*self as u8) >> (index * 8)
+    }
+
+    fn radix_key(&self) -> u128 {
+        *self as u128
+    }
+
+    fn from_radix_key(key: u128) -> Option<Self> {
+        Some(key != 0)
     }
 }
 
@@ -109,7 +268,21 @@ impl RadixDigits for f32 {
     fn get_digit(&self, index: u8) -> u8 {
         let mut b = self.to_bits() as i32;
         b ^= (b >> 31) | i32::MIN;
-        (b as u32 >> index * 8) as u8
+        (b as u32 >> (index * 8)) as u8
+    }
+
+    fn histogram(elements: &[Self], index: u8) -> [usize; 256] {
+        histogram::histogram_32(elements, index, KeyTransform::Float)
+    }
+
+    unsafe fn field_histogram<E>(elements: &[E], offset: usize, index: u8) -> [usize; 256] {
+        histogram::field_histogram_32(elements, offset, index, KeyTransform::Float)
+    }
+
+    fn radix_key(&self) -> u128 {
+        let mut b = self.to_bits() as i32;
+        b ^= (b >> 31) | i32::MIN;
+        b as u32 as u128
     }
 }
 
@@ -119,7 +292,21 @@ impl RadixDigits for f64 {
     fn get_digit(&self, index: u8) -> u8 {
         let mut b = self.to_bits() as i64;
         b ^= (b >> 63) | i64::MIN;
-        (b as u64 >> index * 8) as u8
+        (b as u64 >> (index * 8)) as u8
+    }
+
+    fn histogram(elements: &[Self], index: u8) -> [usize; 256] {
+        histogram::histogram_64(elements, index, KeyTransform::Float)
+    }
+
+    unsafe fn field_histogram<E>(elements: &[E], offset: usize, index: u8) -> [usize; 256] {
+        histogram::field_histogram_64(elements, offset, index, KeyTransform::Float)
+    }
+
+    fn radix_key(&self) -> u128 {
+        let mut b = self.to_bits() as i64;
+        b ^= (b >> 63) | i64::MIN;
+        b as u64 as u128
     }
 }
 
@@ -129,8 +316,23 @@ where
     U: Send + Sync,
 {
     const NUMBER_OF_DIGITS: u8 = T::NUMBER_OF_DIGITS;
+    const KEY_DOMAIN: Option<usize> = T::KEY_DOMAIN;
 
     fn get_digit(&self, index: u8) -> u8 {
         self.0.get_digit(index)
     }
+
+    //Keys are read where they lie within the pairs, so that wide elements get the same
+    //histograms as their keys alone
+    fn histogram(elements: &[Self], index: u8) -> [usize; 256] {
+        unsafe { T::field_histogram(elements, offset_of!(Self, 0), index) }
+    }
+
+    unsafe fn field_histogram<E>(elements: &[E], offset: usize, index: u8) -> [usize; 256] {
+        T::field_histogram(elements, offset + offset_of!(Self, 0), index)
+    }
+
+    fn radix_key(&self) -> u128 {
+        self.0.radix_key()
+    }
 }
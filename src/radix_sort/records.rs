@@ -0,0 +1,117 @@
+use super::{
+    lsd::{lsd_sort, KeyDigits, PassDigits},
+    runner::{ChunkRunner, Chunks},
+    scratch::TempBuffer,
+    RadixSortOptions,
+};
+use std::{ops::Range, ptr::copy_nonoverlapping};
+
+//Big-endian digits of the key of a record, read through the index of the record
+struct RecordDigits<'a> {
+    bytes: &'a [u8],
+    record_len: usize,
+    key: Range<usize>,
+}
+
+impl PassDigits<usize> for RecordDigits<'_> {
+    fn digit(&self, record_index: &usize, index: u8) -> u8 {
+        self.bytes[record_index * self.record_len + self.key.end - 1 - index as usize]
+    }
+}
+
+//Sorts records of `record_len` bytes by the bytes at `key` within every record, compared as a
+//big-endian number, keeping records with equal keys in their order
+pub fn sort_records(bytes: &mut [u8], record_len: usize, key: Range<usize>) {
+    assert!(
+        record_len > 0 && bytes.len().is_multiple_of(record_len),
+        "buffer is not made of {record_len}-byte records"
+    );
+    assert!(
+        key.start <= key.end && key.end <= record_len,
+        "key {key:?} out of a {record_len}-byte record"
+    );
+    let number_of_records = bytes.len() / record_len;
+    if number_of_records < 2 {
+        return;
+    }
+    let options = RadixSortOptions::from_env_cached();
+    let chunks = Chunks::new(number_of_records, options, ChunkRunner::Pool);
+    let record = |record_index: usize| {
+        let start = record_index * record_len;
+        &bytes[start..start + record_len]
+    };
+    //Keys of up to 16 bytes travel with their index, longer ones are read through it in every pass
+    let order = if key.len() <= size_of::<u128>() {
+        let mut pairs = chunks
+            .run(|chunk_index| {
+                chunks
+                    .range(chunk_index)
+                    .map(|record_index| {
+                        let key_value = record(record_index)[key.clone()]
+                            .iter()
+                            .fold(0, |key_value, &byte| key_value << 8 | byte as u128);
+                        (key_value, record_index)
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .concat();
+        //Only the digits the key was packed into, the higher ones being zero
+        lsd_sort(&mut pairs, options, &chunks, &KeyDigits, 0..key.len() as u8);
+        pairs
+            .into_iter()
+            .map(|(_, record_index)| record_index)
+            .collect()
+    } else {
+        let mut record_indices = (0..number_of_records).collect::<Vec<_>>();
+        //Keys longer than the digits a pass can index are sorted by windows of up to 255 bytes,
+        //lowest first, every sort keeping the order of the windows sorted before it
+        let mut window_end = key.end;
+        while window_end > key.start {
+            let window = window_end.saturating_sub(u8::MAX as usize).max(key.start)..window_end;
+            let number_of_digits = window.len() as u8;
+            let digits = RecordDigits {
+                bytes,
+                record_len,
+                key: window.clone(),
+            };
+            lsd_sort(
+                &mut record_indices,
+                options,
+                &chunks,
+                &digits,
+                0..number_of_digits,
+            );
+            window_end = window.start;
+        }
+        record_indices
+    };
+    gather_records(bytes, record_len, &chunks, &order);
+}
+
+fn gather_records(bytes: &mut [u8], record_len: usize, chunks: &Chunks, order: &[usize]) {
+    let temp = TempBuffer::<u8>::new(bytes.len());
+    let temp_slice = temp.as_slice();
+    let records = &*bytes;
+    chunks.run(|chunk_index| {
+        for record_index in chunks.range(chunk_index) {
+            unsafe {
+                copy_nonoverlapping(
+                    records[order[record_index] * record_len..].as_ptr(),
+                    &temp_slice[record_index * record_len] as *const u8 as *mut u8,
+                    record_len,
+                );
+            }
+        }
+    });
+    chunks.run(|chunk_index| {
+        let range = chunks.range(chunk_index);
+        let byte_range = range.start * record_len..range.end * record_len;
+        unsafe {
+            copy_nonoverlapping(
+                temp_slice[byte_range.clone()].as_ptr(),
+                records[byte_range.start..].as_ptr() as *mut u8,
+                byte_range.len(),
+            );
+        }
+    });
+}
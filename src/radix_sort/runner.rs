@@ -0,0 +1,81 @@
+use super::RadixSortOptions;
+use rayon::{
+    broadcast, current_num_threads,
+    iter::{IntoParallelIterator, ParallelIterator},
+};
+use std::ops::Range;
+
+//How the tasks of a pass are spread over threads
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum ChunkRunner {
+    //Everything on the calling thread
+    Caller,
+    //Work stealing on the current pool
+    Pool,
+    //Consecutive chunks go to the same worker of the current pool in every pass
+    Pinned,
+}
+
+impl ChunkRunner {
+    //Results are returned in the order of chunk indices
+    pub(crate) fn run<R, F>(self, number_of_chunks: usize, task: F) -> Vec<R>
+    where
+        R: Send,
+        F: Fn(usize) -> R + Sync,
+    {
+        match self {
+            ChunkRunner::Caller => (0..number_of_chunks).map(task).collect(),
+            ChunkRunner::Pool => (0..number_of_chunks).into_par_iter().map(&task).collect(),
+            ChunkRunner::Pinned => {
+                let chunks_per_worker = number_of_chunks.div_ceil(current_num_threads());
+                broadcast(|context| {
+                    let first_chunk = (context.index() * chunks_per_worker).min(number_of_chunks);
+                    let last_chunk = (first_chunk + chunks_per_worker).min(number_of_chunks);
+                    (first_chunk..last_chunk).map(&task).collect::<Vec<_>>()
+                })
+                .into_iter()
+                .flatten()
+                .collect()
+            }
+        }
+    }
+}
+
+//Split of the input into contiguous chunks, one task each
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct Chunks {
+    pub(crate) len: usize,
+    pub(crate) elements_per_chunk: usize,
+    pub(crate) number_of_chunks: usize,
+    pub(crate) runner: ChunkRunner,
+}
+
+impl Chunks {
+    pub(crate) fn new(len: usize, options: &RadixSortOptions, runner: ChunkRunner) -> Self {
+        let elements_per_chunk = len.div_ceil(options.number_of_chunks(len)).max(1);
+        let number_of_chunks = len.div_ceil(elements_per_chunk);
+        Self {
+            len,
+            elements_per_chunk,
+            number_of_chunks,
+            runner: if number_of_chunks > 1 {
+                runner
+            } else {
+                ChunkRunner::Caller
+            },
+        }
+    }
+
+    pub(crate) fn range(&self, chunk_index: usize) -> Range<usize> {
+        (chunk_index * self.elements_per_chunk).min(self.len)
+            ..((chunk_index + 1) * self.elements_per_chunk).min(self.len)
+    }
+
+    pub(crate) fn run<R, F>(&self, task: F) -> Vec<R>
+    where
+        R: Send,
+        F: Fn(usize) -> R + Sync,
+    {
+        self.runner.run(self.number_of_chunks, task)
+    }
+}
@@ -0,0 +1,103 @@
+use std::{mem::MaybeUninit, slice};
+
+const PAGE_SIZE: usize = 4096;
+#[cfg(target_os = "linux")]
+const HUGE_PAGE_THRESHOLD: usize = 32 << 20;
+
+//Temp memory of a sort, mapped with transparent huge pages when large
+pub(crate) enum TempBuffer<T> {
+    Heap(Vec<MaybeUninit<T>>),
+    #[cfg(target_os = "linux")]
+    Mapped {
+        ptr: *mut MaybeUninit<T>,
+        len: usize,
+    },
+}
+
+unsafe impl<T: Send> Send for TempBuffer<T> {}
+unsafe impl<T: Sync> Sync for TempBuffer<T> {}
+
+impl<T> TempBuffer<T> {
+    pub(crate) fn new(len: usize) -> Self {
+        #[cfg(target_os = "linux")]
+        if len * size_of::<T>() >= HUGE_PAGE_THRESHOLD {
+            if let Some(buffer) = Self::map(len) {
+                return buffer;
+            }
+        }
+        let mut temp = Vec::with_capacity(len);
+        unsafe {
+            temp.set_len(len);
+        }
+        TempBuffer::Heap(temp)
+    }
+
+    #[cfg(target_os = "linux")]
+    fn map(len: usize) -> Option<Self> {
+        let ptr = unsafe {
+            libc::mmap(
+                std::ptr::null_mut(),
+                len * size_of::<T>(),
+                libc::PROT_READ | libc::PROT_WRITE,
+                libc::MAP_PRIVATE | libc::MAP_ANONYMOUS,
+                -1,
+                0,
+            )
+        };
+        if ptr == libc::MAP_FAILED {
+            return None;
+        }
+        //Huge pages are only a hint, the mapping works without them
+        unsafe {
+            libc::madvise(ptr, len * size_of::<T>(), libc::MADV_HUGEPAGE);
+        }
+        Some(TempBuffer::Mapped {
+            ptr: ptr as *mut MaybeUninit<T>,
+            len,
+        })
+    }
+
+    pub(crate) fn as_slice(&self) -> &[T] {
+        let (ptr, len) = match self {
+            TempBuffer::Heap(temp) => (temp.as_ptr(), temp.len()),
+            #[cfg(target_os = "linux")]
+            TempBuffer::Mapped { ptr, len } => (*ptr as *const MaybeUninit<T>, *len),
+        };
+        unsafe { slice::from_raw_parts(ptr as *const T, len) }
+    }
+
+    pub(crate) fn as_mut_slice(&mut self) -> &mut [MaybeUninit<T>] {
+        match self {
+            TempBuffer::Heap(temp) => temp.as_mut_slice(),
+            #[cfg(target_os = "linux")]
+            TempBuffer::Mapped { ptr, len } => unsafe { slice::from_raw_parts_mut(*ptr, *len) },
+        }
+    }
+}
+
+impl<T> Drop for TempBuffer<T> {
+    fn drop(&mut self) {
+        #[cfg(target_os = "linux")]
+        if let TempBuffer::Mapped { ptr, len } = self {
+            unsafe {
+                libc::munmap(*ptr as *mut libc::c_void, *len * size_of::<T>());
+            }
+        }
+    }
+}
+
+//Touches every page of the region, so that it is faulted in by the calling thread
+pub(crate) fn prefault_pages<T>(region: &mut [MaybeUninit<T>]) {
+    let region_as_bytes = unsafe {
+        slice::from_raw_parts_mut(
+            region.as_mut_ptr() as *mut MaybeUninit<u8>,
+            size_of_val(region),
+        )
+    };
+    region_as_bytes
+        .iter_mut()
+        .step_by(PAGE_SIZE)
+        .for_each(|byte| {
+            byte.write(0);
+        });
+}
@@ -0,0 +1,152 @@
+use super::RadixDigits;
+use rayon::current_num_threads;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Strategy {
+    SingleThread,
+    ThreadPerDigit,
+    NativeThreads,
+    Prefault,
+    Rayon,
+    Buffered,
+    Pipelined,
+    Hybrid,
+    //Picks one of the above using the default cost model
+    Auto,
+}
+
+impl Strategy {
+    pub const VARIANTS: [Strategy; 8] = [
+        Strategy::SingleThread,
+        Strategy::ThreadPerDigit,
+        Strategy::NativeThreads,
+        Strategy::Prefault,
+        Strategy::Rayon,
+        Strategy::Buffered,
+        Strategy::Pipelined,
+        Strategy::Hybrid,
+    ];
+
+    pub fn resolve<T: RadixDigits>(self, len: usize) -> Strategy {
+        match self {
+            Strategy::Auto => CostModel::default().choose::<T>(len, current_num_threads()),
+            strategy => strategy,
+        }
+    }
+
+    fn index(self) -> usize {
+        Strategy::VARIANTS
+            .iter()
+            .position(|variant| *variant == self)
+            .expect("Auto has no cost of its own")
+    }
+
+    fn is_parallel(self) -> bool {
+        !matches!(self, Strategy::SingleThread | Strategy::ThreadPerDigit)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct StrategyCost {
+    //Fixed cost of a single pass
+    pub overhead_ns: f64,
+    //Cost of moving one byte in a single pass on a single thread
+    pub ns_per_byte: f64,
+}
+
+impl StrategyCost {
+    //Linear fit through two timings of a whole sort
+    pub fn fit(
+        strategy: Strategy,
+        (small_bytes, small_secs): (usize, f64),
+        (large_bytes, large_secs): (usize, f64),
+        passes: u8,
+        threads: usize,
+    ) -> Self {
+        let passes = passes as f64;
+        let small_ns = small_secs * 1e9 / passes;
+        let large_ns = large_secs * 1e9 / passes;
+        let slope = ((large_ns - small_ns) / (large_bytes - small_bytes) as f64).max(0.0);
+        let parallelism = if strategy.is_parallel() { threads } else { 1 };
+        Self {
+            overhead_ns: (small_ns - slope * small_bytes as f64).max(0.0),
+            ns_per_byte: slope * parallelism as f64,
+        }
+    }
+}
+
+//Per strategy costs, in the order of Strategy::VARIANTS
+#[derive(Clone, Debug, PartialEq)]
+pub struct CostModel {
+    pub costs: [StrategyCost; 8],
+}
+
+impl Default for CostModel {
+    //Calibrated with `cargo run -r -- calibrate`
+    fn default() -> Self {
+        Self {
+            costs: [
+                StrategyCost {
+                    overhead_ns: 0.0,
+                    ns_per_byte: 0.8,
+                },
+                StrategyCost {
+                    overhead_ns: 23000.0,
+                    ns_per_byte: 0.85,
+                },
+                StrategyCost {
+                    overhead_ns: 38000.0,
+                    ns_per_byte: 0.82,
+                },
+                StrategyCost {
+                    overhead_ns: 31000.0,
+                    ns_per_byte: 0.87,
+                },
+                StrategyCost {
+                    overhead_ns: 14000.0,
+                    ns_per_byte: 0.82,
+                },
+                StrategyCost {
+                    overhead_ns: 164000.0,
+                    ns_per_byte: 0.44,
+                },
+                StrategyCost {
+                    overhead_ns: 6200.0,
+                    ns_per_byte: 0.39,
+                },
+                StrategyCost {
+                    overhead_ns: 48000.0,
+                    ns_per_byte: 0.36,
+                },
+            ],
+        }
+    }
+}
+
+impl CostModel {
+    pub fn estimate_ns(
+        &self,
+        strategy: Strategy,
+        len: usize,
+        element_size: usize,
+        passes: u8,
+        threads: usize,
+    ) -> f64 {
+        let cost = self.costs[strategy.index()];
+        let parallelism = if strategy.is_parallel() { threads } else { 1 };
+        let bytes_per_thread = (len * element_size) as f64 / parallelism.max(1) as f64;
+        passes as f64 * (cost.overhead_ns + cost.ns_per_byte * bytes_per_thread)
+    }
+
+    pub fn choose<T: RadixDigits>(&self, len: usize, threads: usize) -> Strategy {
+        Strategy::VARIANTS
+            .into_iter()
+            .min_by(|a, b| {
+                let cost = |strategy| {
+                    self.estimate_ns(strategy, len, size_of::<T>(), T::NUMBER_OF_DIGITS, threads)
+                };
+                cost(*a).total_cmp(&cost(*b))
+            })
+            .expect("there is at least one strategy")
+    }
+}
@@ -0,0 +1,113 @@
+use std::{marker::PhantomData, mem::MaybeUninit, ptr::copy_nonoverlapping};
+
+pub(crate) const CACHE_LINE: usize = 64;
+
+#[repr(C, align(64))]
+struct CacheLine([u8; CACHE_LINE]);
+
+//Uninitialized memory starting at a cache line boundary
+pub(crate) struct AlignedBuffer<T> {
+    lines: Vec<MaybeUninit<CacheLine>>,
+    element: PhantomData<T>,
+}
+
+impl<T> AlignedBuffer<T> {
+    pub(crate) fn new(len: usize) -> Self {
+        assert!(align_of::<T>() <= CACHE_LINE);
+        let number_of_lines = (len * size_of::<T>()).div_ceil(CACHE_LINE);
+        let mut lines = Vec::with_capacity(number_of_lines);
+        unsafe {
+            lines.set_len(number_of_lines);
+        }
+        Self {
+            lines,
+            element: PhantomData,
+        }
+    }
+
+    pub(crate) fn as_mut_ptr(&mut self) -> *mut T {
+        self.lines.as_mut_ptr() as *mut T
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum FlushKind {
+    Copy,
+    #[cfg(target_arch = "x86_64")]
+    StreamSse2,
+    #[cfg(target_arch = "x86_64")]
+    StreamAvx,
+}
+
+impl FlushKind {
+    pub(crate) fn detect(streaming_stores: bool) -> Self {
+        if !streaming_stores {
+            return FlushKind::Copy;
+        }
+        #[cfg(target_arch = "x86_64")]
+        {
+            if is_x86_feature_detected!("avx") {
+                return FlushKind::StreamAvx;
+            }
+            FlushKind::StreamSse2
+        }
+        #[cfg(not(target_arch = "x86_64"))]
+        FlushKind::Copy
+    }
+
+    //Aligned blocks of the destination are written with non-temporal stores, the rest is copied
+    pub(crate) unsafe fn flush<T>(self, src: *const T, dst: *mut T, len: usize) {
+        match self {
+            FlushKind::Copy => copy_nonoverlapping(src, dst, len),
+            #[cfg(target_arch = "x86_64")]
+            FlushKind::StreamSse2 => {
+                stream_sse2(src as *const u8, dst as *mut u8, len * size_of::<T>())
+            }
+            #[cfg(target_arch = "x86_64")]
+            FlushKind::StreamAvx => {
+                stream_avx(src as *const u8, dst as *mut u8, len * size_of::<T>())
+            }
+        }
+    }
+
+    //Non-temporal stores have to be fenced before other threads read the destination
+    pub(crate) fn fence(self) {
+        #[cfg(target_arch = "x86_64")]
+        if self != FlushKind::Copy {
+            unsafe { std::arch::x86_64::_mm_sfence() };
+        }
+    }
+}
+
+#[cfg(target_arch = "x86_64")]
+unsafe fn stream_sse2(src: *const u8, dst: *mut u8, len: usize) {
+    use std::arch::x86_64::{__m128i, _mm_loadu_si128, _mm_stream_si128};
+    let head = dst.align_offset(size_of::<__m128i>()).min(len);
+    copy_nonoverlapping(src, dst, head);
+    let mut offset = head;
+    while offset + size_of::<__m128i>() <= len {
+        _mm_stream_si128(
+            dst.add(offset) as *mut __m128i,
+            _mm_loadu_si128(src.add(offset) as *const __m128i),
+        );
+        offset += size_of::<__m128i>();
+    }
+    copy_nonoverlapping(src.add(offset), dst.add(offset), len - offset);
+}
+
+#[cfg(target_arch = "x86_64")]
+#[target_feature(enable = "avx")]
+unsafe fn stream_avx(src: *const u8, dst: *mut u8, len: usize) {
+    use std::arch::x86_64::{__m256i, _mm256_loadu_si256, _mm256_stream_si256};
+    let head = dst.align_offset(size_of::<__m256i>()).min(len);
+    copy_nonoverlapping(src, dst, head);
+    let mut offset = head;
+    while offset + size_of::<__m256i>() <= len {
+        _mm256_stream_si256(
+            dst.add(offset) as *mut __m256i,
+            _mm256_loadu_si256(src.add(offset) as *const __m256i),
+        );
+        offset += size_of::<__m256i>();
+    }
+    copy_nonoverlapping(src.add(offset), dst.add(offset), len - offset);
+}
@@ -1,8 +1,22 @@
 use rand::{seq::SliceRandom, thread_rng, Rng};
 use rand_distr::{Distribution, Standard, Uniform};
-use std::cmp::Ordering;
+use rayon::{
+    iter::{IntoParallelRefMutIterator, ParallelIterator},
+    ThreadPoolBuilder,
+};
+use std::{cmp::Ordering, env, fs, process, slice};
 
-use super::RadixSort;
+use crate::distr::{ZipfU32, ZipfU64};
+
+use super::{
+    apply_inverse_permutation, apply_permutation, counting_sort, external_sort, invert_permutation,
+    lsd::sort_keys,
+    numa::parse_cpu_list,
+    permutation::permute_in_place,
+    runner::{ChunkRunner, Chunks},
+    sort_file_in_place, sort_file_in_place_at, sort_records, ExternalSortOptions, Permutation,
+    RadixDigits, RadixSort, RadixSortCopyOnly, RadixSortOptions, Strategy,
+};
 
 fn verify_sorted<T>(data: &[T], original: Option<&mut [T]>)
 where
@@ -22,6 +36,33 @@ where
     }
 }
 
+fn verify_histograms<T: RadixDigits>(data: &[T]) {
+    for digit_index in 0..T::NUMBER_OF_DIGITS {
+        let mut bin_histogram = [0; 256];
+        for element in data {
+            bin_histogram[element.get_digit(digit_index) as usize] += 1;
+        }
+        if T::histogram(data, digit_index) != bin_histogram {
+            panic!("Histogram of digit {} is wrong!", digit_index);
+        }
+    }
+}
+
+fn verify_field_histograms<E, K: RadixDigits>(
+    elements: &[E],
+    offset: usize,
+    key: impl Fn(&E) -> K,
+) {
+    for digit_index in 0..K::NUMBER_OF_DIGITS {
+        let mut bin_histogram = [0; 256];
+        for element in elements {
+            bin_histogram[key(element).get_digit(digit_index) as usize] += 1;
+        }
+        let field_histogram = unsafe { K::field_histogram(elements, offset, digit_index) };
+        assert_eq!(field_histogram, bin_histogram);
+    }
+}
+
 #[test]
 fn radix_sort_u8() {
     let mut data_original = vec![0u8; 1e6 as usize];
@@ -185,3 +226,665 @@ fn radix_sort_tuple() {
         panic!("Not sorted properly!");
     }
 }
+
+#[test]
+fn counting_sorts() {
+    let mut data_original = Standard
+        .sample_iter(thread_rng())
+        .take(1e5 as usize)
+        .collect::<Vec<bool>>();
+    let mut data_sorted = data_original.clone();
+    data_sorted.radix_sort();
+    verify_sorted(&data_sorted, Some(&mut data_original));
+    let mut data_original = Standard
+        .sample_iter(thread_rng())
+        .take(1e6 as usize)
+        .collect::<Vec<i16>>();
+    let mut data_sorted = data_original.clone();
+    counting_sort(&mut data_sorted);
+    verify_sorted(&data_sorted, Some(&mut data_original));
+    //Keys without a domain are radix sorted
+    let mut data_original = Standard
+        .sample_iter(thread_rng())
+        .take(1e5 as usize)
+        .collect::<Vec<u32>>();
+    let mut data_sorted = data_original.clone();
+    counting_sort(&mut data_sorted);
+    verify_sorted(&data_sorted, Some(&mut data_original));
+    let mut data_original = Standard
+        .sample_iter(thread_rng())
+        .take(1e6 as usize)
+        .collect::<Vec<(i8, u32)>>();
+    let mut data_sorted = data_original.clone();
+    data_sorted.radix_sort_with(&RadixSortOptions::new().threads(4));
+    data_original.sort_by_key(|e| e.0);
+    if data_sorted != data_original {
+        panic!("Not sorted properly!");
+    }
+}
+
+#[test]
+fn radix_sort_in_pool() {
+    let pool = ThreadPoolBuilder::new().num_threads(3).build().unwrap();
+    let mut data_original = vec![0u64; 1e6 as usize];
+    rand::thread_rng().fill(data_original.as_mut_slice());
+    let mut data_sorted = data_original.clone();
+    data_sorted.radix_sort_in(&pool);
+    verify_sorted(&data_sorted, Some(&mut data_original));
+}
+
+#[test]
+fn radix_sort_nested() {
+    let mut data_original = (0..8)
+        .map(|_| {
+            let mut data = vec![0u32; 1e5 as usize];
+            rand::thread_rng().fill(data.as_mut_slice());
+            data
+        })
+        .collect::<Vec<_>>();
+    let mut data_sorted = data_original.clone();
+    data_sorted
+        .par_iter_mut()
+        .for_each(|data| data.radix_sort());
+    for (data_sorted, data_original) in data_sorted.iter().zip(&mut data_original) {
+        verify_sorted(data_sorted, Some(data_original));
+    }
+}
+
+#[test]
+fn radix_sort_with_options() {
+    let mut data_original = vec![0u32; 1e6 as usize];
+    rand::thread_rng().fill(data_original.as_mut_slice());
+    for options in [
+        RadixSortOptions::new().threads(1),
+        RadixSortOptions::new().threads(7).chunk_multiplier(3),
+        //Prefix sums scanned by several groups of chunks, the last one partial
+        RadixSortOptions::new().threads(25).chunk_multiplier(5),
+        RadixSortOptions::new().buffer_bytes(0).prefault(false),
+        RadixSortOptions::new().small_input_threshold(usize::MAX),
+    ] {
+        let mut data_sorted = data_original.clone();
+        data_sorted.radix_sort_with(&options);
+        verify_sorted(&data_sorted, Some(&mut data_original.clone()));
+    }
+}
+
+#[test]
+fn radix_sort_strategies() {
+    let mut data_original = vec![0u32; 1e5 as usize];
+    rand::thread_rng().fill(data_original.as_mut_slice());
+    for strategy in Strategy::VARIANTS.into_iter().chain([Strategy::Auto]) {
+        let mut data_sorted = data_original.clone();
+        data_sorted.radix_sort_strategy(strategy);
+        verify_sorted(&data_sorted, Some(&mut data_original.clone()));
+        for len in 0..3 {
+            let mut data_sorted = data_original[..len].to_vec();
+            data_sorted.radix_sort_strategy(strategy);
+            verify_sorted(&data_sorted, Some(&mut data_original[..len].to_vec()));
+        }
+    }
+    //Histograms of the next digit counted by every worker for the chunks it writes to
+    let pool = ThreadPoolBuilder::new().num_threads(5).build().unwrap();
+    let mut data_sorted = data_original.clone();
+    pool.install(|| data_sorted.radix_sort6());
+    verify_sorted(&data_sorted, Some(&mut data_original));
+}
+
+#[test]
+fn radix_sort_hybrid() {
+    let mut data_original = ZipfU64::new(1.1)
+        .sample_iter(thread_rng())
+        .take(1e6 as usize)
+        .collect::<Vec<u64>>();
+    let mut data_sorted = data_original.clone();
+    data_sorted.radix_sort7();
+    verify_sorted(&data_sorted, Some(&mut data_original));
+    let mut data_original = Standard
+        .sample_iter(thread_rng())
+        .take(1e6 as usize)
+        .collect::<Vec<(u16, u32)>>();
+    let mut data_sorted = data_original.clone();
+    data_sorted.radix_sort7();
+    data_original.sort_by_key(|e| e.0);
+    if data_sorted != data_original {
+        panic!("Not sorted properly!");
+    }
+    let mut data_original = vec![0u8; 1e5 as usize];
+    rand::thread_rng().fill(data_original.as_mut_slice());
+    let mut data_sorted = data_original.clone();
+    data_sorted.radix_sort7();
+    verify_sorted(&data_sorted, Some(&mut data_original));
+}
+
+#[test]
+fn radix_sort_dominant_runs() {
+    let options = RadixSortOptions::new().threads(4);
+    let mut data_original = ZipfU32::new(2.0)
+        .sample_iter(thread_rng())
+        .take(1e6 as usize)
+        .collect::<Vec<(u32, u32)>>();
+    data_original
+        .iter_mut()
+        .enumerate()
+        .for_each(|(index, e)| e.1 = index as u32);
+    let mut data_sorted = data_original.clone();
+    data_sorted.radix_sort_with(&options);
+    data_original.sort_by_key(|e| e.0);
+    if data_sorted != data_original {
+        panic!("Not sorted properly!");
+    }
+    //Top digit of every chunk in a single bin
+    let mut data_original = (0..1e6 as u32)
+        .map(|index| (index * 4 / 1e6 as u32) << 24 | thread_rng().gen_range(0..1 << 24))
+        .collect::<Vec<_>>();
+    let mut data_sorted = data_original.clone();
+    data_sorted.radix_sort_with(&options);
+    verify_sorted(&data_sorted, Some(&mut data_original));
+}
+
+#[test]
+fn radix_sort_indirect() {
+    for options in [
+        RadixSortOptions::new().indirect_threshold(0),
+        RadixSortOptions::new().indirect_threshold(0).threads(4),
+    ] {
+        let mut data_original = Standard
+            .sample_iter(thread_rng())
+            .take(1e5 as usize)
+            .collect::<Vec<(u64, [u64; 15])>>();
+        data_original.iter_mut().for_each(|e| e.0 %= 1000);
+        let mut data_sorted = data_original.clone();
+        data_sorted.radix_sort_with(&options);
+        data_original.sort_by_key(|e| e.0);
+        if data_sorted != data_original {
+            panic!("Not sorted properly!");
+        }
+        let mut data_original = vec![0i16; 1e5 as usize];
+        rand::thread_rng().fill(data_original.as_mut_slice());
+        let mut data_sorted = data_original.clone();
+        data_sorted.radix_sort_with(&options);
+        verify_sorted(&data_sorted, Some(&mut data_original));
+    }
+}
+
+#[test]
+fn permutations_in_place() {
+    let len = 1e5 as usize;
+    let mut shuffled = (0..len).collect::<Vec<_>>();
+    shuffled.shuffle(&mut thread_rng());
+    //Long and short cycles, fixed points, and cycles crossing every chunk
+    let mut mixed = (0..len).collect::<Vec<_>>();
+    mixed[..len / 2].shuffle(&mut thread_rng());
+    mixed[len / 2..].reverse();
+    let permutations = [
+        shuffled,
+        (0..len).rev().collect(),
+        (0..len).map(|index| (index + 1) % len).collect(),
+        (0..len).map(|index| (index + len / 3) % len).collect(),
+        (0..len).collect(),
+        mixed,
+    ];
+    for threads in [1, 4, 13] {
+        let pool = ThreadPoolBuilder::new()
+            .num_threads(threads)
+            .build()
+            .unwrap();
+        let options = RadixSortOptions::new().threads(threads).chunk_multiplier(3);
+        for permutation in &permutations {
+            let mut data = (0..len).map(|index| index.to_string()).collect::<Vec<_>>();
+            let chunks = Chunks::new(len, &options, ChunkRunner::Pool);
+            pool.install(|| permute_in_place(&mut data, &chunks, |index| permutation[index]));
+            assert!(data
+                .iter()
+                .zip(permutation)
+                .all(|(element, &source)| *element == source.to_string()));
+        }
+    }
+}
+
+#[test]
+fn permutations() {
+    let keys = Standard
+        .sample_iter(thread_rng())
+        .take(1e5 as usize)
+        .collect::<Vec<u32>>();
+    let mut pairs = keys
+        .iter()
+        .enumerate()
+        .map(|(index, &key)| (key, index))
+        .collect::<Vec<_>>();
+    pairs.radix_sort();
+    let permutation = pairs.iter().map(|e| e.1).collect::<Vec<_>>();
+    let mut keys_sorted = keys.clone();
+    apply_permutation(&mut keys_sorted, &permutation);
+    verify_sorted(&keys_sorted, Some(&mut keys.clone()));
+    let mut names = keys.iter().map(|key| key.to_string()).collect::<Vec<_>>();
+    apply_permutation(&mut names, &permutation);
+    assert!(names
+        .iter()
+        .zip(&keys_sorted)
+        .all(|(name, key)| *name == key.to_string()));
+    apply_inverse_permutation(&mut keys_sorted, &permutation);
+    assert_eq!(keys_sorted, keys);
+    let inverse = invert_permutation(&permutation);
+    assert_eq!(invert_permutation(&inverse), permutation);
+    assert!((0..permutation.len()).all(|index| inverse[permutation[index]] == index));
+    //Checked once, with the scratch memory reused between columns
+    let permutation = Permutation::new(permutation);
+    let mut scratch = Vec::new();
+    let mut keys_sorted = keys.clone();
+    permutation.apply(&mut keys_sorted, &mut scratch);
+    verify_sorted(&keys_sorted, Some(&mut keys.clone()));
+    let mut other_keys = keys.iter().map(|key| !key).collect::<Vec<_>>();
+    permutation.apply(&mut other_keys, &mut scratch);
+    assert!(other_keys.iter().zip(&keys_sorted).all(|(a, b)| *a == !b));
+    permutation.apply_inverse(&mut keys_sorted, &mut scratch);
+    assert_eq!(keys_sorted, keys);
+    let mut names_sorted = names.clone();
+    permutation.apply_inverse(&mut names_sorted, &mut Vec::new());
+    assert_eq!(
+        names_sorted,
+        keys.iter().map(|key| key.to_string()).collect::<Vec<_>>()
+    );
+}
+
+#[test]
+#[should_panic]
+fn permutation_with_repeated_index() {
+    apply_permutation(&mut [1, 2, 3], &[0, 2, 2]);
+}
+
+#[test]
+#[should_panic]
+fn inverse_permutation_with_index_out_of_range() {
+    apply_inverse_permutation(&mut [1, 2, 3], &[0, 1, 3]);
+}
+
+#[test]
+#[should_panic]
+fn permutation_checked_once_with_repeated_index() {
+    Permutation::new(vec![1, 1, 0]);
+}
+
+#[test]
+fn records() {
+    const RECORD_LEN: usize = 100;
+    let mut bytes = vec![0u8; RECORD_LEN * 1e5 as usize];
+    rand::thread_rng().fill(bytes.as_mut_slice());
+    //Few distinct keys, so that the order of equal keys is checked too
+    bytes
+        .chunks_mut(RECORD_LEN)
+        .for_each(|record| record[..8].fill(0));
+    for key in [0..10, 70..100, 0..0] {
+        let mut records_sorted = bytes.clone();
+        sort_records(&mut records_sorted, RECORD_LEN, key.clone());
+        let mut records_expected = bytes.chunks(RECORD_LEN).collect::<Vec<_>>();
+        records_expected.sort_by_key(|record| &record[key.clone()]);
+        if records_sorted != records_expected.concat() {
+            panic!("Not sorted properly by bytes {key:?}!");
+        }
+    }
+}
+
+#[test]
+fn records_with_long_key() {
+    const RECORD_LEN: usize = 600;
+    let mut bytes = vec![0u8; RECORD_LEN * 1e4 as usize];
+    rand::thread_rng().fill(bytes.as_mut_slice());
+    //Keys differing in few bytes both in the first window sorted and in the last one
+    bytes.chunks_mut(RECORD_LEN).for_each(|record| {
+        record[..300].fill(0);
+        record[10] %= 4;
+        record[299] %= 4;
+    });
+    for key in [0..400, 0..255, 0..256, 0..510, 0..RECORD_LEN] {
+        let mut records_sorted = bytes.clone();
+        sort_records(&mut records_sorted, RECORD_LEN, key.clone());
+        let mut records_expected = bytes.chunks(RECORD_LEN).collect::<Vec<_>>();
+        records_expected.sort_by_key(|record| &record[key.clone()]);
+        if records_sorted != records_expected.concat() {
+            panic!("Not sorted properly by bytes {key:?}!");
+        }
+    }
+}
+
+#[test]
+fn external() {
+    let temp_dir = env::temp_dir().join(format!("radix_sort_external_{}", process::id()));
+    fs::create_dir_all(&temp_dir).unwrap();
+    let input = temp_dir.join("input");
+    let output = temp_dir.join("output");
+    //Few distinct keys, so that the order of equal keys across runs is checked too
+    let data_original = Standard
+        .sample_iter(thread_rng())
+        .take(1e5 as usize)
+        .map(|(key, value): (u64, u64)| (key % 1000, value))
+        .collect::<Vec<_>>();
+    fs::write(&input, unsafe {
+        slice::from_raw_parts(
+            data_original.as_ptr() as *const u8,
+            size_of_val(data_original.as_slice()),
+        )
+    })
+    .unwrap();
+    let mut data_expected = data_original.clone();
+    data_expected.sort_by_key(|e| e.0);
+    //Budgets for more runs than are merged at once, fewer runs and a single one
+    for memory_budget in [1 << 13, 1 << 16, 1 << 30] {
+        let options = ExternalSortOptions::new()
+            .memory_budget(memory_budget)
+            .temp_dir(temp_dir.join("runs"));
+        external_sort::<(u64, u64)>(&input, &output, &options).unwrap();
+        let bytes = fs::read(&output).unwrap();
+        let data_sorted = bytes
+            .chunks(16)
+            .map(|e| {
+                (
+                    u64::from_ne_bytes(e[..8].try_into().unwrap()),
+                    u64::from_ne_bytes(e[8..].try_into().unwrap()),
+                )
+            })
+            .collect::<Vec<_>>();
+        if data_sorted != data_expected {
+            panic!("Not sorted properly with a budget of {memory_budget} bytes!");
+        }
+    }
+    assert_eq!(fs::read_dir(temp_dir.join("runs")).unwrap().count(), 0);
+    fs::remove_dir_all(&temp_dir).unwrap();
+}
+
+#[test]
+fn file_in_place() {
+    let path = env::temp_dir().join(format!("radix_sort_in_place_{}", process::id()));
+    let mut data = Standard
+        .sample_iter(thread_rng())
+        .take(1e5 as usize)
+        .collect::<Vec<u32>>();
+    fs::write(&path, unsafe {
+        slice::from_raw_parts(data.as_ptr() as *const u8, size_of_val(data.as_slice()))
+    })
+    .unwrap();
+    sort_file_in_place::<u32>(&path).unwrap();
+    data.sort_unstable();
+    let bytes = fs::read(&path).unwrap();
+    let data_sorted = bytes
+        .chunks(4)
+        .map(|e| u32::from_ne_bytes(e.try_into().unwrap()))
+        .collect::<Vec<_>>();
+    assert_eq!(data_sorted, data);
+    //Files cut within an element are rejected
+    fs::write(&path, [0u8; 6]).unwrap();
+    assert!(sort_file_in_place::<u32>(&path).is_err());
+    fs::write(&path, []).unwrap();
+    sort_file_in_place::<u32>(&path).unwrap();
+    //Elements after a header, which is left as it is
+    fs::write(
+        &path,
+        [[9, 9, 9, 9], [3, 0, 0, 0], [1, 0, 0, 0], [2, 0, 0, 0]].concat(),
+    )
+    .unwrap();
+    assert!(sort_file_in_place_at::<u32>(&path, 2).is_err());
+    sort_file_in_place_at::<u32>(&path, 4).unwrap();
+    assert_eq!(
+        fs::read(&path).unwrap(),
+        [[9, 9, 9, 9], [1, 0, 0, 0], [2, 0, 0, 0], [3, 0, 0, 0]].concat()
+    );
+    fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn radix_sort_streaming_stores() {
+    let options = RadixSortOptions::new().streaming_stores(true).threads(4);
+    let mut data_original = vec![0u128; 1e6 as usize];
+    rand::thread_rng().fill(data_original.as_mut_slice());
+    let mut data_sorted = data_original.clone();
+    data_sorted.radix_sort_with(&options);
+    verify_sorted(&data_sorted, Some(&mut data_original));
+    let mut data_original = Standard
+        .sample_iter(thread_rng())
+        .take(1e6 as usize)
+        .collect::<Vec<(u32, [u8; 8])>>();
+    let mut data_sorted = data_original.clone();
+    data_sorted.radix_sort_with(&options);
+    data_original.sort_by_key(|e| e.0);
+    if data_sorted != data_original {
+        panic!("Not sorted properly!");
+    }
+}
+
+#[test]
+fn radix_sort_key_range() {
+    let options = RadixSortOptions::new().key_range(true).threads(4);
+    let mut data_original = Uniform::new(1_700_000_000u64, 1_800_000_000)
+        .sample_iter(thread_rng())
+        .take(1e6 as usize)
+        .collect::<Vec<_>>();
+    let mut data_sorted = data_original.clone();
+    data_sorted.radix_sort_with(&options);
+    verify_sorted(&data_sorted, Some(&mut data_original));
+    let mut data_original = Uniform::new(-1000i64, 1000)
+        .sample_iter(thread_rng())
+        .take(1e5 as usize)
+        .collect::<Vec<_>>();
+    let mut data_sorted = data_original.clone();
+    data_sorted.radix_sort_with(&options);
+    verify_sorted(&data_sorted, Some(&mut data_original));
+    let mut data_sorted = vec![-1.5f64, 3.25, -0.0, 0.0, 2.0, -7.0];
+    data_sorted.radix_sort_with(&options);
+    assert_eq!(data_sorted, [-7.0, -1.5, -0.0, 0.0, 2.0, 3.25]);
+    let mut data_sorted = vec![42u32; 1000];
+    data_sorted.radix_sort_with(&options);
+    assert!(data_sorted.iter().all(|&e| e == 42));
+}
+
+#[test]
+fn radix_sort_bits() {
+    let mut data_original = vec![0u64; 1e6 as usize];
+    rand::thread_rng().fill(data_original.as_mut_slice());
+    for bits in [48..64, 0..48, 4..13, 7..8, 5..5, 0..64] {
+        let mut data_sorted = data_original.clone();
+        data_sorted.radix_sort_bits(bits.clone());
+        let mask = u64::MAX.checked_shr(64 - bits.len() as u32).unwrap_or(0);
+        let mut data_expected = data_original.clone();
+        data_expected.sort_by_key(|e| (e >> bits.start) & mask);
+        if data_sorted != data_expected {
+            panic!("Not sorted properly by bits {bits:?}!");
+        }
+    }
+}
+
+#[test]
+fn radix_sort_presorted() {
+    let options = RadixSortOptions::new().threads(4);
+    let mut data_original = vec![0u64; 1e6 as usize];
+    rand::thread_rng().fill(data_original.as_mut_slice());
+    data_original.sort();
+    let mut data_sorted = data_original.clone();
+    data_sorted.radix_sort_with(&options);
+    verify_sorted(&data_sorted, Some(&mut data_original.clone()));
+    data_original.dedup();
+    data_original.reverse();
+    let mut data_sorted = data_original.clone();
+    data_sorted.radix_sort_with(&options);
+    verify_sorted(&data_sorted, Some(&mut data_original));
+    //Every chunk sorted on its own, but not the whole input
+    let mut data_original = (0..1e6 as u32).map(|e| e % 1000).collect::<Vec<_>>();
+    let mut data_sorted = data_original.clone();
+    data_sorted.radix_sort_with(&options);
+    verify_sorted(&data_sorted, Some(&mut data_original));
+    //Sorted but for one pair straddling the blocks the order is checked in
+    let mut data_original = (0..1e6 as u32).collect::<Vec<_>>();
+    data_original.swap(1 << 14, (1 << 14) - 1);
+    let mut data_sorted = data_original.clone();
+    data_sorted.radix_sort_with(&options);
+    verify_sorted(&data_sorted, Some(&mut data_original));
+    //Elements larger than the blocks the order is checked in, sorted directly
+    let options = options.clone().indirect_threshold(usize::MAX);
+    let mut data_original = vec![(0u64, [0u8; 70000]); 300];
+    for (index, element) in data_original.iter_mut().enumerate() {
+        element.0 = index as u64;
+        element.1[69999] = index as u8;
+    }
+    let mut data_sorted = data_original.clone();
+    data_sorted.radix_sort_with(&options);
+    if data_sorted != data_original {
+        panic!("Not sorted properly!");
+    }
+    data_original.swap(100, 200);
+    let mut data_sorted = data_original.clone();
+    data_sorted.radix_sort_with(&options);
+    data_original.sort_by_key(|e| e.0);
+    if data_sorted != data_original {
+        panic!("Not sorted properly!");
+    }
+    let options = RadixSortOptions::new().threads(4);
+    //Descending but not strictly, reversing would break stability
+    let data_original = (0..1e5 as u32)
+        .rev()
+        .map(|e| (e / 2, e))
+        .collect::<Vec<_>>();
+    let mut data_sorted = data_original.clone();
+    data_sorted.radix_sort_with(&options);
+    let mut data_expected = data_original.clone();
+    data_expected.sort_by_key(|e| e.0);
+    if data_sorted != data_expected {
+        panic!("Not sorted properly!");
+    }
+}
+
+#[test]
+fn histograms() {
+    let size = 1e5 as usize + 3;
+    verify_histograms(
+        &Standard
+            .sample_iter(thread_rng())
+            .take(size)
+            .collect::<Vec<u32>>(),
+    );
+    verify_histograms(
+        &Standard
+            .sample_iter(thread_rng())
+            .take(size)
+            .collect::<Vec<i32>>(),
+    );
+    verify_histograms(
+        &Standard
+            .sample_iter(thread_rng())
+            .take(size)
+            .collect::<Vec<u64>>(),
+    );
+    verify_histograms(
+        &Standard
+            .sample_iter(thread_rng())
+            .take(size)
+            .collect::<Vec<i64>>(),
+    );
+    verify_histograms(
+        &Uniform::new(-1e6f32, 1e6)
+            .sample_iter(thread_rng())
+            .take(size)
+            .collect::<Vec<_>>(),
+    );
+    verify_histograms(
+        &Uniform::new(-1e6f64, 1e6)
+            .sample_iter(thread_rng())
+            .take(size)
+            .collect::<Vec<_>>(),
+    );
+    verify_histograms(
+        &Standard
+            .sample_iter(thread_rng())
+            .take(size)
+            .collect::<Vec<(u16, u8)>>(),
+    );
+    //Keys of pairs, read from both halves, at any offset and through nested pairs
+    verify_histograms(
+        &Standard
+            .sample_iter(thread_rng())
+            .take(size)
+            .collect::<Vec<(u32, f32)>>(),
+    );
+    verify_histograms(
+        &Standard
+            .sample_iter(thread_rng())
+            .take(size)
+            .collect::<Vec<(i64, u64)>>(),
+    );
+    verify_histograms(
+        &Uniform::new(-1e6f64, 1e6)
+            .sample_iter(thread_rng())
+            .take(size)
+            .map(|key| (key, size))
+            .collect::<Vec<_>>(),
+    );
+    verify_histograms(
+        &Standard
+            .sample_iter(thread_rng())
+            .take(size)
+            .collect::<Vec<(i32, u64)>>(),
+    );
+    verify_histograms(
+        &Standard
+            .sample_iter(thread_rng())
+            .take(size)
+            .collect::<Vec<(u64, [u8; 13])>>(),
+    );
+    verify_histograms(
+        &Standard
+            .sample_iter(thread_rng())
+            .take(size)
+            .collect::<Vec<((u8, u32), (u32, u64))>>(),
+    );
+    //Keys in either half of elements of two keys, and within elements of three
+    let elements = Standard
+        .sample_iter(thread_rng())
+        .take(size)
+        .collect::<Vec<[u64; 2]>>();
+    verify_field_histograms(&elements, 0, |element| element[0]);
+    verify_field_histograms(&elements, 8, |element| element[1]);
+    let elements = Standard
+        .sample_iter(thread_rng())
+        .take(size)
+        .collect::<Vec<[f32; 2]>>();
+    verify_field_histograms(&elements, 0, |element| element[0]);
+    verify_field_histograms(&elements, 4, |element| element[1]);
+    let elements = Standard
+        .sample_iter(thread_rng())
+        .take(size)
+        .collect::<Vec<[i32; 3]>>();
+    verify_field_histograms(&elements, 4, |element| element[1]);
+    verify_field_histograms(&elements, 8, |element| element[2]);
+}
+
+#[test]
+fn radix_sort_mapped_temp() {
+    let mut data_original = vec![0u64; 5e6 as usize];
+    rand::thread_rng().fill(data_original.as_mut_slice());
+    let mut data_sorted = data_original.clone();
+    data_sorted.radix_sort();
+    verify_sorted(&data_sorted, Some(&mut data_original));
+}
+
+#[test]
+fn radix_sort_numa() {
+    let mut data_original = vec![0u32; 1e6 as usize];
+    rand::thread_rng().fill(data_original.as_mut_slice());
+    let mut data_sorted = data_original.clone();
+    data_sorted.radix_sort_with(&RadixSortOptions::new().numa(true));
+    verify_sorted(&data_sorted, Some(&mut data_original.clone()));
+    let pool = ThreadPoolBuilder::new().num_threads(3).build().unwrap();
+    let mut data_sorted = data_original.clone();
+    let options = RadixSortOptions::new().chunk_multiplier(2);
+    pool.install(|| sort_keys(&mut data_sorted, &options, ChunkRunner::Pinned));
+    verify_sorted(&data_sorted, Some(&mut data_original));
+}
+
+#[test]
+fn cpu_lists() {
+    assert_eq!(
+        parse_cpu_list("0-3,8-9,12\n"),
+        Some(vec![0, 1, 2, 3, 8, 9, 12])
+    );
+    assert_eq!(parse_cpu_list("\n"), Some(vec![]));
+    assert_eq!(parse_cpu_list("0-a"), None);
+}